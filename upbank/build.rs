@@ -24,10 +24,20 @@ fn main() {
     // println!("cargo:rerun-if-changed=data/rfc4217.xml");
     let mut scope = Scope::new();
     scope.import("serde", "Deserialize");
-    let cc_enum = scope.new_enum("CurrencyCode").vis("pub").derive("Debug").derive("Deserialize");
+    scope.import("serde", "Serialize");
+    let cc_enum = scope
+        .new_enum("CurrencyCode")
+        .vis("pub")
+        .derive("Debug")
+        .derive("Deserialize")
+        .derive("Serialize")
+        .derive("Clone")
+        .derive("Copy")
+        .derive("PartialEq")
+        .derive("Eq");
     let currency_codes = get_currency_codes();
     for currency_code in &currency_codes {
-        let variant = Variant::new(&currency_code);
+        let variant = Variant::new(currency_code);
         cc_enum.push_variant(variant);
     }
 
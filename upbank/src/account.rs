@@ -1,205 +1,123 @@
-use crate::{currency, error, resource, response, setter, transaction};
-use log::*;
-use serde::Deserialize;
+use crate::{currency, endpoint, error, resource, resource_id, transaction};
+use serde::{Deserialize, Serialize};
 use strum_macros::Display;
-use url::{form_urlencoded, Url};
+use url::Url;
+
+resource_id!(
+    /// ID of an account.
+    ///
+    /// IDs of different resources can't be mixed up:
+    ///
+    /// ```compile_fail,E0308
+    /// use upbank::ResourceClient;
+    /// let client = upbank::Client::default_client("token".to_string());
+    /// let id = upbank::transaction::TransactionId::new("not-an-account");
+    /// client.account.get(&id);
+    /// ```
+    AccountId
+);
 
 pub struct AccountClient {
-    client: reqwest::blocking::Client,
-    base_url: Url,
-    token: String,
+    endpoint: endpoint::Endpoint,
 }
 
-#[derive(Deserialize, Debug, Display)]
+#[derive(Deserialize, Serialize, Debug, Display, PartialEq, Clone)]
 pub enum AccountType {
     SAVER,
     TRANSACTIONAL,
 }
 
-#[derive(Deserialize, Debug)]
+impl std::str::FromStr for AccountType {
+    type Err = error::ClientError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_lowercase()[..] {
+            "saver" => Ok(AccountType::SAVER),
+            "transactional" => Ok(AccountType::TRANSACTIONAL),
+            _ => Err(error::ClientError::ConversionError {
+                value: s.into(),
+                reason: "Must be one of [saver, transactional] (case insensitive)".into(),
+            }),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Display, PartialEq, Clone)]
+pub enum OwnershipType {
+    INDIVIDUAL,
+    JOINT,
+}
+
+impl std::str::FromStr for OwnershipType {
+    type Err = error::ClientError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_lowercase()[..] {
+            "individual" => Ok(OwnershipType::INDIVIDUAL),
+            "joint" => Ok(OwnershipType::JOINT),
+            _ => Err(error::ClientError::ConversionError {
+                value: s.into(),
+                reason: "Must be one of [individual, joint] (case insensitive)".into(),
+            }),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Attributes {
     pub display_name: String,
     pub account_type: AccountType,
+    pub ownership_type: OwnershipType,
     pub balance: currency::Money,
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-pub struct RelatedLinks {
-    pub related: String,
-}
-
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-pub struct TransactionLinks {
-    pub links: RelatedLinks,
+pub struct Relationships {
+    pub transactions: resource::LinksOnly,
 }
 
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-pub struct Relationships {
-    pub transactions: TransactionLinks,
+impl resource::Related for Relationships {
+    fn relationships(&self) -> Vec<resource::RelationshipRef<'_>> {
+        vec![self.transactions.to_ref("transactions")]
+    }
 }
 
-pub type Account = resource::Resource<Attributes, Relationships>;
+pub type Account = resource::Resource<Attributes, Relationships, AccountId>;
 
 impl AccountClient {
-    pub fn new(base_url: Url, token: String) -> Self {
-        AccountClient {
-            client: reqwest::blocking::Client::new(),
-            base_url,
-            token,
-        }
+    pub fn new<T: Into<crate::secret::Token>>(base_url: Url, token: T) -> Self {
+        Self::from_endpoint(endpoint::Endpoint::new(base_url, token))
     }
 
-    pub fn list(&self) -> AccountListRequestBuilder {
-        AccountListRequestBuilder {
-            size: None,
-            client: &self.client,
-            base_url: self.base_url.clone(),
-            token: self.token.clone(),
-        }
+    pub fn from_endpoint(endpoint: endpoint::Endpoint) -> Self {
+        AccountClient { endpoint }
     }
 
-    pub fn get(&self, id: String) -> error::Result<response::Response<Account>> {
-        let account_url = self.base_url.join(&id)?;
-        debug!("Sending account get request to {}", account_url.to_string());
-        let resp = self
-            .client
-            .get(account_url)
-            .bearer_auth(&self.token)
-            .send()?
-            .json::<response::Response<Account>>()?;
-        trace!("Get accounts responded with {:?}", resp);
-        Ok(resp)
-    }
-
-    pub fn transactions(&self, id: String) -> TransactionListRequestBuilder {
-        TransactionListRequestBuilder {
-            base_url: self.base_url.clone(),
-            client: &self.client,
-            token: self.token.clone(),
-            id,
-
-            size: None,
-            status: None,
-            since: None,
-            until: None,
-            category: None,
-            tag: None,
-        }
+    pub fn transactions(&self, id: &AccountId) -> transaction::ListRequestBuilder {
+        transaction::ListRequestBuilder::new(&self.endpoint, &format!("{}/transactions", id))
     }
 }
 
-pub struct AccountListRequestBuilder<'a> {
-    size: Option<u32>,
-    base_url: Url,
-    client: &'a reqwest::blocking::Client,
-    token: String,
-}
+impl endpoint::ResourceClient<Account> for AccountClient {
+    type Id = AccountId;
 
-impl<'a> AccountListRequestBuilder<'a> {
-    setter!(size, u32);
-
-    pub fn exec(&self) -> error::Result<response::Response<Vec<Account>>> {
-        let mut query = vec![];
-        if let Some(size) = self.size {
-            query.push(("page[size]", size))
-        }
-        debug!(
-            "Sending account list request to {}",
-            self.base_url.to_string()
-        );
-        let resp = self
-            .client
-            .get(self.base_url.clone())
-            .bearer_auth(&self.token)
-            .query(&query)
-            .send()?
-            .json::<response::Response<Vec<Account>>>()?;
-        trace!("List accounts responded with {:?}", resp);
-        Ok(resp)
+    fn endpoint(&self) -> &endpoint::Endpoint {
+        &self.endpoint
     }
 }
 
-pub struct TransactionListRequestBuilder<'a> {
-    base_url: Url,
-    client: &'a reqwest::blocking::Client,
-    token: String,
-    id: String,
-
-    size: Option<u32>,
-    status: Option<transaction::Status>,
-    since: Option<chrono::DateTime<chrono::Utc>>,
-    until: Option<chrono::DateTime<chrono::Utc>>,
-    category: Option<String>,
-    tag: Option<String>,
-}
-
-impl<'a> TransactionListRequestBuilder<'a> {
-    setter!(size, u32);
-    setter!(status, transaction::Status);
-    setter!(since, chrono::DateTime<chrono::Utc>);
-    setter!(until, chrono::DateTime<chrono::Utc>);
-    setter!(category, String);
-    setter!(tag, String);
-
-    pub fn exec(&self) -> error::Result<response::Response<Vec<transaction::Transaction>>> {
-        let mut query = vec![];
-
-        if let Some(size) = self.size {
-            let value: String =
-                form_urlencoded::byte_serialize(size.to_string().as_bytes()).collect();
-            query.push(("page[size]", value))
-        }
-
-        if let Some(status) = &self.status {
-            let value: String =
-                form_urlencoded::byte_serialize(status.to_string().as_bytes()).collect();
-            query.push(("filter[status]", value))
-        }
+pub type AccountListRequestBuilder = endpoint::ListRequestBuilder<Account>;
 
-        if let Some(since) = self.since {
-            let value: String =
-                form_urlencoded::byte_serialize(since.to_rfc3339().as_bytes()).collect();
-            query.push(("filter[since]", value));
-        }
-
-        if let Some(until) = self.until {
-            let value: String =
-                form_urlencoded::byte_serialize(until.to_rfc3339().as_bytes()).collect();
-            query.push(("filter[until]", value));
-        }
-
-        if let Some(category) = &self.category {
-            let value: String = form_urlencoded::byte_serialize(category.as_bytes()).collect();
-            query.push(("filter[category]", value));
-        }
-
-        if let Some(tag) = &self.tag {
-            let value: String = form_urlencoded::byte_serialize(tag.as_bytes()).collect();
-            query.push(("filter[tag]", value));
-        }
+impl AccountListRequestBuilder {
+    pub fn account_type(&mut self, account_type: AccountType) -> &mut Self {
+        self.param("filter[accountType]", account_type.to_string())
+    }
 
-        // Append "/" to the ID so that appending "transactions" after doesn't
-        // stomp it.
-        let id_part = format!("{}/", self.id);
-        let url = self.base_url.join(&id_part)?.join("transactions")?;
-        debug!(
-            "Sending account transactions get request to {}",
-            url.to_string()
-        );
-        let resp = self
-            .client
-            .get(url)
-            .bearer_auth(&self.token)
-            .query(&query)
-            .send()?
-            .json::<response::Response<Vec<transaction::Transaction>>>()?;
-        trace!("Get account transactions responded with {:?}", resp);
-        Ok(resp)
+    pub fn ownership_type(&mut self, ownership_type: OwnershipType) -> &mut Self {
+        self.param("filter[ownershipType]", ownership_type.to_string())
     }
 }
 
@@ -207,6 +125,7 @@ impl<'a> TransactionListRequestBuilder<'a> {
 mod test {
     use super::Account;
     use crate::response::SuccessfulResponse;
+    use crate::test_round_trip;
 
     #[test]
     fn test_account_de() {
@@ -227,4 +146,7 @@ mod test {
         let contents = std::fs::read_to_string(path).unwrap();
         let _ = serde_json::from_str::<SuccessfulResponse<Vec<Account>>>(&contents).unwrap();
     }
+
+    test_round_trip!(test_account_round_trip, "account.json", Account);
+    test_round_trip!(test_accounts_round_trip, "account_list.json", Vec<Account>);
 }
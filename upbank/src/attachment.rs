@@ -0,0 +1,123 @@
+use crate::endpoint::{self, ResourceClient};
+use crate::{error, resource, resource_id, transaction};
+use log::*;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+resource_id!(
+    /// ID of an attachment.
+    AttachmentId
+);
+
+pub struct AttachmentClient {
+    endpoint: endpoint::Endpoint,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Attributes {
+    pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Pre-signed URL the file can be downloaded from. It does not need the
+    /// bearer token but is only valid until `file_url_expires_at`.
+    #[serde(rename = "fileURL")]
+    pub file_url: Option<String>,
+    #[serde(rename = "fileURLExpiresAt")]
+    pub file_url_expires_at: chrono::DateTime<chrono::Utc>,
+    pub file_extension: Option<String>,
+    pub file_content_type: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Relationships {
+    pub transaction: resource::ToOne<transaction::TransactionId>,
+}
+
+impl resource::Related for Relationships {
+    fn relationships(&self) -> Vec<resource::RelationshipRef<'_>> {
+        vec![self.transaction.to_ref("transaction")]
+    }
+}
+
+pub type Attachment = resource::Resource<Attributes, Relationships, AttachmentId>;
+
+impl AttachmentClient {
+    pub fn new<T: Into<crate::secret::Token>>(base_url: Url, token: T) -> Self {
+        Self::from_endpoint(endpoint::Endpoint::new(base_url, token))
+    }
+
+    pub fn from_endpoint(endpoint: endpoint::Endpoint) -> Self {
+        AttachmentClient { endpoint }
+    }
+
+    /// Download the attachment's file into `dest`, returning the number of
+    /// bytes written.
+    pub fn download<W: std::io::Write>(
+        &self,
+        attachment: &Attachment,
+        dest: &mut W,
+    ) -> error::Result<u64> {
+        let file_url = attachment.attributes.file_url.as_ref().ok_or_else(|| {
+            error::ClientError::InternalError(format!(
+                "Attachment {} has no file URL",
+                attachment.id
+            ))
+        })?;
+        debug!("Downloading attachment {}", attachment.id);
+        // The file URL is pre-signed so we mustn't send the token along with it.
+        let written = self
+            .endpoint
+            .send_presigned(self.endpoint.client().get(file_url))
+            .and_then(|resp| Ok(resp.error_for_status()?.copy_to(dest)?))
+            .map_err(|e| match e {
+                error::ClientError::RequestError(e) => hide_signature(e),
+                e => e,
+            })?;
+        trace!(
+            "Downloaded {} bytes for attachment {}",
+            written,
+            attachment.id
+        );
+        Ok(written)
+    }
+}
+
+/// reqwest's errors include the URL they're for, which for a pre-signed URL
+/// would hand out a working link to the file.
+fn hide_signature(e: reqwest::Error) -> error::ClientError {
+    let message = match e.url() {
+        Some(url) => {
+            let mut shown = url.clone();
+            shown.set_query(None);
+            e.to_string().replace(url.as_str(), shown.as_str())
+        }
+        None => e.to_string(),
+    };
+    error::ClientError::DownloadError(message)
+}
+
+impl ResourceClient<Attachment> for AttachmentClient {
+    type Id = AttachmentId;
+
+    fn endpoint(&self) -> &endpoint::Endpoint {
+        &self.endpoint
+    }
+}
+
+pub type ListRequestBuilder = endpoint::ListRequestBuilder<Attachment>;
+
+#[cfg(test)]
+mod test {
+    use super::Attachment;
+    use crate::response::SuccessfulResponse;
+    use crate::{test_deserialization, test_round_trip};
+
+    test_deserialization!(test_de, "attachment.json", Attachment);
+    test_deserialization!(test_multi_de, "attachment_list.json", Vec<Attachment>);
+    test_round_trip!(test_round_trip, "attachment.json", Attachment);
+    test_round_trip!(
+        test_multi_round_trip,
+        "attachment_list.json",
+        Vec<Attachment>
+    );
+}
@@ -1,126 +1,296 @@
-use crate::{error::Result, resource::Resource, response::Response, setter};
-use log::*;
-use serde::Deserialize;
+use crate::{
+    endpoint::{Endpoint, ListRequestBuilder, ResourceClient},
+    error::{ClientError, Result},
+    resource::{Related, RelationshipRef, Resource, ToMany, ToOne},
+    resource_id,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use url::Url;
 
+resource_id!(
+    /// ID of a category, e.g. "restaurants-and-cafes".
+    CategoryId
+);
+
 pub struct CategoryClient {
-    client: reqwest::blocking::Client,
-    base_url: Url,
-    token: String,
+    endpoint: Endpoint,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Attributes {
     pub name: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Relationships {
-    pub parent: ParentRelationships,
-    pub children: ChildRelationships,
+    pub parent: ToOne<CategoryId>,
+    pub children: ToMany<CategoryId>,
 }
 
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-pub struct ParentRelationships {
-    pub data: Option<Relationship>,
-    pub links: Option<RelatedLinks>,
+impl Related for Relationships {
+    fn relationships(&self) -> Vec<RelationshipRef<'_>> {
+        vec![
+            self.parent.to_ref("parent"),
+            self.children.to_ref("children"),
+        ]
+    }
 }
 
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-pub struct ChildRelationships {
-    pub data: Vec<Relationship>,
-    pub links: Option<RelatedLinks>,
-}
+pub type Category = Resource<Attributes, Relationships, CategoryId>;
 
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-pub struct RelatedLinks {
-    pub related: String,
-}
+impl CategoryClient {
+    pub fn new<T: Into<crate::secret::Token>>(base_url: Url, token: T) -> Self {
+        Self::from_endpoint(Endpoint::new(base_url, token))
+    }
 
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-pub struct Relationship {
-    #[serde(rename = "type")]
-    pub relationship_type: String,
-    pub id: String,
+    pub fn from_endpoint(endpoint: Endpoint) -> Self {
+        CategoryClient { endpoint }
+    }
+
+    /// Build the category hierarchy from a single list request.
+    pub fn tree(&self) -> Result<CategoryTree> {
+        match self.list().exec()? {
+            Response::Ok(categories) => Ok(CategoryTree::new(categories.data)),
+            Response::Err(e) => Err(ClientError::UpBankError(e)),
+        }
+    }
 }
 
-pub type Category = Resource<Attributes, Relationships>;
+impl ResourceClient<Category> for CategoryClient {
+    type Id = CategoryId;
 
-impl CategoryClient {
-    pub fn new(base_url: Url, token: String) -> Self {
-        CategoryClient {
-            client: reqwest::blocking::Client::new(),
-            base_url,
-            token,
-        }
+    fn endpoint(&self) -> &Endpoint {
+        &self.endpoint
     }
-    pub fn get(&self, id: &str) -> Result<Response<Category>> {
-        let url = self.base_url.join(&id)?;
-        debug!("Sending category get request to {}", url.to_string());
-        let resp = self
-            .client
-            .get(url)
-            .bearer_auth(&self.token)
-            .send()?
-            .json::<Response<Category>>()?;
-        trace!("Get category responded with {:?}", resp);
-        Ok(resp)
-    }
-
-    pub fn list(&self) -> ListRequestBuilder {
-        ListRequestBuilder {
-            parent: None,
-            base_url: self.base_url.clone(),
-            client: &self.client,
-            token: self.token.clone(),
-        }
+}
+
+impl ListRequestBuilder<Category> {
+    pub fn parent(&mut self, parent: CategoryId) -> &mut Self {
+        self.param("filter[parent]", parent.into_inner())
     }
 }
 
-pub struct ListRequestBuilder<'a> {
-    parent: Option<String>,
-    base_url: Url,
-    client: &'a reqwest::blocking::Client,
-    token: String,
+/// Separator used between category names in `CategoryTree::path`.
+pub const PATH_SEPARATOR: &str = " > ";
+
+/// The category hierarchy, navigable by parent and children.
+#[derive(Debug)]
+pub struct CategoryTree {
+    categories: Vec<Category>,
+    index: HashMap<CategoryId, usize>,
 }
 
-impl<'a> ListRequestBuilder<'a> {
-    setter!(parent, String);
+impl CategoryTree {
+    pub fn new(categories: Vec<Category>) -> Self {
+        let index = categories
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (c.id.clone(), i))
+            .collect();
+        CategoryTree { categories, index }
+    }
+
+    /// Look up a category by its ID.
+    pub fn get(&self, id: &CategoryId) -> Option<&Category> {
+        self.index.get(id).map(|i| &self.categories[*i])
+    }
+
+    /// Look up a category by its name (case insensitive).
+    pub fn get_by_name(&self, name: &str) -> Option<&Category> {
+        self.categories
+            .iter()
+            .find(|c| c.attributes.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Look up a category by its ID, falling back to its name.
+    pub fn find(&self, id_or_name: &str) -> Option<&Category> {
+        self.index
+            .get(id_or_name)
+            .map(|i| &self.categories[*i])
+            .or_else(|| self.get_by_name(id_or_name))
+    }
+
+    /// The categories whose parent isn't in the tree, in the order UpBank
+    /// returned them. These are the top level categories, or the children of
+    /// the parent a list was filtered by.
+    pub fn roots(&self) -> Vec<&Category> {
+        self.categories
+            .iter()
+            .filter(|c| self.parent(&c.id).is_none())
+            .collect()
+    }
+
+    pub fn parent(&self, id: &CategoryId) -> Option<&Category> {
+        self.get(id)?
+            .relationships
+            .parent
+            .data
+            .as_ref()
+            .and_then(|p| self.get(&p.id))
+    }
 
-    pub fn exec(&self) -> Result<Response<Vec<Category>>> {
-        let mut query = vec![];
-        if let Some(parent) = &self.parent {
-            query.push(("filter[parent]", parent));
+    pub fn children(&self, id: &CategoryId) -> Vec<&Category> {
+        self.get(id).map_or_else(Vec::new, |c| {
+            c.relationships
+                .children
+                .data
+                .iter()
+                .filter_map(|child| self.get(&child.id))
+                .collect()
+        })
+    }
+
+    /// The category's ancestors starting from the root, not including the
+    /// category itself.
+    pub fn ancestors(&self, id: &CategoryId) -> Vec<&Category> {
+        let mut ancestors = vec![];
+        let mut current = id;
+        while let Some(parent) = self.parent(current) {
+            // Guard against a malformed hierarchy sending us in circles.
+            if ancestors.iter().any(|a: &&Category| a.id == parent.id) {
+                break;
+            }
+            ancestors.push(parent);
+            current = &parent.id;
         }
+        ancestors.reverse();
+        ancestors
+    }
 
-        debug!(
-            "Sending category list request to {}",
-            self.base_url.to_string()
-        );
-        let resp = self
-            .client
-            .get(self.base_url.clone())
-            .bearer_auth(&self.token)
-            .query(&query)
-            .send()?
-            .json::<Response<Vec<Category>>>()?;
-        trace!("List category responded with {:?}", resp);
-        Ok(resp)
+    /// The names from the root down to the category, e.g.
+    /// "Good Life > Restaurants & Cafes".
+    pub fn path(&self, id: &CategoryId) -> Option<String> {
+        let category = self.get(id)?;
+        let mut names: Vec<&str> = self
+            .ancestors(id)
+            .iter()
+            .map(|c| c.attributes.name.as_str())
+            .collect();
+        names.push(&category.attributes.name);
+        Some(names.join(PATH_SEPARATOR))
+    }
+
+    /// Every category paired with its depth, parents before their children.
+    pub fn walk(&self) -> Vec<(usize, &Category)> {
+        let mut walked = vec![];
+        let mut stack: Vec<(usize, &Category)> =
+            self.roots().into_iter().rev().map(|c| (0, c)).collect();
+        while let Some((depth, category)) = stack.pop() {
+            walked.push((depth, category));
+            for child in self.children(&category.id).into_iter().rev() {
+                stack.push((depth + 1, child));
+            }
+        }
+        walked
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Category> {
+        self.categories.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.categories.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.categories.is_empty()
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::Category;
+    use super::{Category, CategoryId, CategoryTree};
     use crate::response::SuccessfulResponse;
-    use crate::test_deserialization;
+    use crate::{test_deserialization, test_round_trip};
 
     test_deserialization!(test_de, "category.json", Category);
     test_deserialization!(test_multi_de, "category_list.json", Vec<Category>);
+    test_round_trip!(test_round_trip, "category.json", Category);
+    test_round_trip!(test_multi_round_trip, "category_list.json", Vec<Category>);
+
+    fn categories() -> Vec<Category> {
+        crate::macros::fixture::<SuccessfulResponse<Vec<Category>>>("category_list.json").data
+    }
+
+    fn tree() -> CategoryTree {
+        CategoryTree::new(categories())
+    }
+
+    #[test]
+    fn test_tree_navigation() {
+        let tree = tree();
+        let roots: Vec<&str> = tree.roots().iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(roots, vec!["good-life"]);
+        let children: Vec<&str> = tree
+            .children(&CategoryId::new("good-life"))
+            .iter()
+            .map(|c| c.id.as_str())
+            .collect();
+        assert_eq!(children, vec!["hobbies", "restaurants-and-cafes"]);
+        assert_eq!(
+            tree.parent(&CategoryId::new("hobbies")).unwrap().id,
+            CategoryId::new("good-life")
+        );
+        assert!(tree.parent(&CategoryId::new("good-life")).is_none());
+    }
+
+    #[test]
+    fn test_tree_lookup() {
+        let tree = tree();
+        assert_eq!(tree.find("hobbies").unwrap().attributes.name, "Hobbies");
+        assert_eq!(
+            tree.find("restaurants & cafes").unwrap().id,
+            CategoryId::new("restaurants-and-cafes")
+        );
+        assert!(tree.find("groceries").is_none());
+    }
+
+    #[test]
+    fn test_tree_path() {
+        let tree = tree();
+        assert_eq!(
+            tree.path(&CategoryId::new("restaurants-and-cafes"))
+                .unwrap(),
+            "Good Life > Restaurants & Cafes"
+        );
+        assert_eq!(
+            tree.path(&CategoryId::new("good-life")).unwrap(),
+            "Good Life"
+        );
+        let walked: Vec<(usize, &str)> = tree
+            .walk()
+            .into_iter()
+            .map(|(d, c)| (d, c.id.as_str()))
+            .collect();
+        assert_eq!(
+            walked,
+            vec![
+                (0, "good-life"),
+                (1, "hobbies"),
+                (1, "restaurants-and-cafes")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filtered_tree() {
+        // As listed with filter[parent], so every category has a parent
+        // that isn't in the list.
+        let children = categories()
+            .into_iter()
+            .filter(|c| c.relationships.parent.data.is_some())
+            .collect();
+        let tree = CategoryTree::new(children);
+        let walked: Vec<(usize, &str)> = tree
+            .walk()
+            .into_iter()
+            .map(|(d, c)| (d, c.id.as_str()))
+            .collect();
+        assert_eq!(walked, vec![(0, "hobbies"), (0, "restaurants-and-cafes")]);
+        assert_eq!(tree.path(&CategoryId::new("hobbies")).unwrap(), "Hobbies");
+    }
 }
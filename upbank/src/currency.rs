@@ -1,7 +1,7 @@
 use crate::iso4217;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Money {
     pub currency_code: iso4217::CurrencyCode,
@@ -13,4 +13,4 @@ impl ToString for Money {
     fn to_string(&self) -> String {
         format!("{} {}", self.value, self.currency_code)
     }
-}
\ No newline at end of file
+}
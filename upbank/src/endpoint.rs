@@ -0,0 +1,351 @@
+//! Machinery shared by every resource client: sending authenticated
+//! requests, retrying, mapping error responses and paginated listing.
+
+use crate::{error, interceptor, response, secret};
+use log::*;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use url::Url;
+
+/// Number of times a GET request is retried after a rate limit, server error
+/// or connection failure.
+pub const DEFAULT_RETRIES: u32 = 2;
+
+/// Delay before the first retry, doubling with each subsequent retry.
+const RETRY_BACKOFF: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// A base URL and the credentials needed to make requests under it. Cloning
+/// is cheap and the clones share a connection pool and interceptors.
+#[derive(Clone)]
+pub struct Endpoint {
+    client: reqwest::blocking::Client,
+    base_url: Url,
+    token: secret::Token,
+    retries: u32,
+    interceptors: Arc<interceptor::Chain>,
+}
+
+impl Endpoint {
+    pub fn new<T: Into<secret::Token>>(base_url: Url, token: T) -> Self {
+        Endpoint {
+            client: reqwest::blocking::Client::new(),
+            base_url,
+            token: token.into(),
+            retries: DEFAULT_RETRIES,
+            interceptors: Arc::new(interceptor::Chain::default()),
+        }
+    }
+
+    /// The endpoint at `path` under this one, sharing its connection pool,
+    /// token and interceptors.
+    pub fn join(&self, path: &str) -> error::Result<Endpoint> {
+        Ok(Endpoint {
+            base_url: self.url(path)?,
+            ..self.clone()
+        })
+    }
+
+    /// Add an interceptor to this endpoint and every endpoint sharing its
+    /// interceptors.
+    pub(crate) fn add_interceptor(&self, interceptor: Arc<dyn interceptor::Interceptor>) {
+        self.interceptors.add(interceptor);
+    }
+
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    /// The underlying HTTP client, for requests that mustn't carry the token.
+    pub(crate) fn client(&self) -> &reqwest::blocking::Client {
+        &self.client
+    }
+
+    /// Join `path` onto the base URL.
+    pub(crate) fn url(&self, path: &str) -> error::Result<Url> {
+        Ok(self.base_url.join(path)?)
+    }
+
+    /// Start an authenticated request.
+    pub(crate) fn request(
+        &self,
+        method: reqwest::Method,
+        url: Url,
+    ) -> reqwest::blocking::RequestBuilder {
+        // Marks the header as sensitive so it's redacted if the request is
+        // debug printed.
+        self.client
+            .request(method, url)
+            .bearer_auth(self.token.expose_secret())
+    }
+
+    /// Send a request, letting the interceptors see it and its response.
+    /// Every request goes through here.
+    pub(crate) fn send(
+        &self,
+        req: reqwest::blocking::RequestBuilder,
+    ) -> error::Result<reqwest::blocking::Response> {
+        let req = req.build()?;
+        let url = req.url().clone();
+        self.send_as(req, url)
+    }
+
+    /// Send a request to a pre-signed URL. Its query is the signature, so
+    /// logs and interceptors only see the rest of the URL.
+    pub(crate) fn send_presigned(
+        &self,
+        req: reqwest::blocking::RequestBuilder,
+    ) -> error::Result<reqwest::blocking::Response> {
+        let req = req.build()?;
+        let mut url = req.url().clone();
+        url.set_query(None);
+        self.send_as(req, url)
+    }
+
+    /// Send a request, showing it to logs and interceptors as going to
+    /// `shown_url`.
+    fn send_as(
+        &self,
+        req: reqwest::blocking::Request,
+        shown_url: Url,
+    ) -> error::Result<reqwest::blocking::Response> {
+        let url = req.url().clone();
+        let info = interceptor::RequestInfo {
+            id: self.interceptors.next_id(),
+            method: req.method().clone(),
+            url: shown_url.clone(),
+            body_size: req
+                .body()
+                .and_then(|b| b.as_bytes())
+                .map(|b| b.len() as u64),
+        };
+        debug!("Sending {} request to {}", info.method, info.url);
+        self.interceptors.on_request(&info);
+
+        let started = std::time::Instant::now();
+        let result = self.client.execute(req);
+        let response = interceptor::ResponseInfo {
+            status: result.as_ref().ok().map(|r| r.status()),
+            latency: started.elapsed(),
+            body_size: result.as_ref().ok().and_then(|r| r.content_length()),
+            error: result
+                .as_ref()
+                .err()
+                .map(|e| e.to_string().replace(url.as_str(), shown_url.as_str())),
+        };
+        self.interceptors.on_response(&info, &response);
+        if url == shown_url {
+            trace!(
+                "{} request to {} responded with {:?}",
+                info.method,
+                info.url,
+                result
+            );
+        } else {
+            // The response itself would show the URL that's being hidden.
+            trace!(
+                "{} request to {} responded with {:?}",
+                info.method,
+                info.url,
+                response
+            );
+        }
+        Ok(result?)
+    }
+
+    /// Send a GET request, retrying on failures that are likely to be
+    /// transient.
+    pub(crate) fn get<T: DeserializeOwned + Debug>(
+        &self,
+        url: Url,
+    ) -> error::Result<response::Response<T>> {
+        let mut attempt = 0;
+        loop {
+            let result = self.send(self.request(reqwest::Method::GET, url.clone()));
+            let retryable = match &result {
+                Ok(resp) => {
+                    resp.status() == reqwest::StatusCode::TOO_MANY_REQUESTS
+                        || resp.status().is_server_error()
+                }
+                Err(error::ClientError::RequestError(e)) => e.is_timeout() || e.is_connect(),
+                Err(_) => false,
+            };
+            if retryable && attempt < self.retries {
+                let delay = RETRY_BACKOFF * 2u32.pow(attempt);
+                warn!("GET request to {} failed, retrying in {:?}", url, delay);
+                std::thread::sleep(delay);
+                attempt += 1;
+                continue;
+            }
+            return self.parse(result?);
+        }
+    }
+
+    /// Send a request that isn't safe to retry and parse the resource in the
+    /// response.
+    pub(crate) fn fetch<T: DeserializeOwned + Debug>(
+        &self,
+        req: reqwest::blocking::RequestBuilder,
+    ) -> error::Result<response::Response<T>> {
+        self.parse(self.send(req)?)
+    }
+
+    /// Send a request whose successful response has no body, turning error
+    /// responses into `ClientError::UpBankError`.
+    pub(crate) fn execute(&self, req: reqwest::blocking::RequestBuilder) -> error::Result<()> {
+        let resp = self.send(req)?;
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(error::ClientError::UpBankError(
+                resp.json::<error::Error>()?,
+            ))
+        }
+    }
+
+    fn parse<T: DeserializeOwned + Debug>(
+        &self,
+        resp: reqwest::blocking::Response,
+    ) -> error::Result<response::Response<T>> {
+        let resp = if resp.status().is_success() {
+            response::Response::Ok(resp.json::<response::SuccessfulResponse<T>>()?)
+        } else {
+            response::Response::Err(resp.json::<error::Error>()?)
+        };
+        trace!("Parsed response {:?}", resp);
+        Ok(resp)
+    }
+}
+
+/// Implemented by the client of each resource to get fetching by ID and
+/// listing for free.
+pub trait ResourceClient<R: DeserializeOwned + Debug> {
+    type Id: AsRef<str>;
+
+    fn endpoint(&self) -> &Endpoint;
+
+    fn get(&self, id: &Self::Id) -> error::Result<response::Response<R>> {
+        let url = self.endpoint().url(id.as_ref())?;
+        self.endpoint().get(url)
+    }
+
+    fn list(&self) -> ListRequestBuilder<R> {
+        ListRequestBuilder::new(self.endpoint(), "")
+    }
+}
+
+/// Builder for listing resources. Filters specific to a resource are added
+/// by that resource's module.
+///
+/// Builders own a clone of their client's endpoint, so they can be stored,
+/// cloned and sent to other threads.
+pub struct ListRequestBuilder<R> {
+    endpoint: Endpoint,
+    path: String,
+    query: BTreeMap<String, String>,
+    // Builders never hold an `R`, so they're `Send` and `Sync` regardless of
+    // it.
+    resource: PhantomData<fn() -> R>,
+}
+
+impl<R> Clone for ListRequestBuilder<R> {
+    fn clone(&self) -> Self {
+        ListRequestBuilder {
+            endpoint: self.endpoint.clone(),
+            path: self.path.clone(),
+            query: self.query.clone(),
+            resource: PhantomData,
+        }
+    }
+}
+
+impl<R: DeserializeOwned + Debug> ListRequestBuilder<R> {
+    /// `path` is relative to the endpoint's base URL.
+    pub(crate) fn new(endpoint: &Endpoint, path: &str) -> Self {
+        ListRequestBuilder {
+            endpoint: endpoint.clone(),
+            path: path.to_string(),
+            query: BTreeMap::new(),
+            resource: PhantomData,
+        }
+    }
+
+    /// Set a query parameter, replacing any previous value.
+    pub(crate) fn param(&mut self, key: &str, value: String) -> &mut Self {
+        self.query.insert(key.to_string(), value);
+        self
+    }
+
+    /// Number of resources per page.
+    pub fn size(&mut self, size: u32) -> &mut Self {
+        self.param("page[size]", size.to_string())
+    }
+
+    /// The URL of the first page, with the filters encoded in the query.
+    pub fn to_url(&self) -> error::Result<Url> {
+        let mut url = self.endpoint.url(&self.path)?;
+        if !self.query.is_empty() {
+            url.query_pairs_mut().extend_pairs(&self.query);
+        }
+        Ok(url)
+    }
+
+    /// Fetch the first page.
+    pub fn exec(&self) -> error::Result<response::Response<Vec<R>>> {
+        self.endpoint.get(self.to_url()?)
+    }
+
+    /// Iterate over every page, following `links.next`. Iteration stops
+    /// after the first error.
+    pub fn pages(&self) -> Pages<R> {
+        Pages {
+            endpoint: self.endpoint.clone(),
+            next: Some(self.to_url()),
+            resource: PhantomData,
+        }
+    }
+
+    /// Fetch every page, returning all the resources in a single response.
+    pub fn exec_all(&self) -> error::Result<response::Response<Vec<R>>> {
+        let mut data = vec![];
+        for page in self.pages() {
+            match page? {
+                response::Response::Ok(mut page) => data.append(&mut page.data),
+                err => return Ok(err),
+            }
+        }
+        Ok(response::Response::Ok(response::SuccessfulResponse {
+            data,
+            links: None,
+        }))
+    }
+}
+
+/// Iterator over the pages of a list request.
+pub struct Pages<R> {
+    endpoint: Endpoint,
+    next: Option<error::Result<Url>>,
+    resource: PhantomData<fn() -> R>,
+}
+
+impl<R: DeserializeOwned + Debug> Iterator for Pages<R> {
+    type Item = error::Result<response::Response<Vec<R>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let url = match self.next.take()? {
+            Ok(url) => url,
+            Err(e) => return Some(Err(e)),
+        };
+        let page = self.endpoint.get::<Vec<R>>(url);
+        if let Ok(response::Response::Ok(page)) = &page {
+            self.next = page
+                .links
+                .as_ref()
+                .and_then(|l| l.next.as_ref())
+                .map(|next| Ok(Url::parse(next)?));
+        }
+        Some(page)
+    }
+}
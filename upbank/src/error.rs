@@ -1,8 +1,8 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::option::Option;
 use thiserror::Error;
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct Source {
     parameter: Option<String>,
     pointer: Option<String>,
@@ -14,7 +14,7 @@ impl std::fmt::Display for Source {
     }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct ErrorObject {
     pub status: String,
     pub title: String,
@@ -32,7 +32,7 @@ impl std::fmt::Display for ErrorObject {
     }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct Error {
     pub errors: Vec<ErrorObject>,
 }
@@ -63,6 +63,11 @@ pub enum ClientError {
     #[error("Request failed: {0}")]
     RequestError(#[from] reqwest::Error),
 
+    /// Error downloading from a pre-signed URL. It's the request's error
+    /// message with the URL's signature taken out.
+    #[error("Download failed: {0}")]
+    DownloadError(String),
+
     /// Error due to serialization.
     #[error("Serialization failed: {0}")]
     SerializationError(#[from] serde_json::error::Error),
@@ -74,6 +79,10 @@ pub enum ClientError {
     /// Conversion error.
     #[error("Failed to convert {value}: {reason}")]
     ConversionError { value: String, reason: String },
+
+    /// Value rejected before the request was sent.
+    #[error("Invalid value {value}: {reason}")]
+    ValidationError { value: String, reason: String },
 }
 
 pub type Result<T> = std::result::Result<T, ClientError>;
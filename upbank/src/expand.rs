@@ -0,0 +1,133 @@
+use crate::{account, category, error, response, tag, transaction, Client, ResourceClient};
+use log::*;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// A transaction with its relationships resolved into the resources they
+/// point at.
+#[derive(Debug)]
+pub struct ExpandedTransaction {
+    pub transaction: transaction::Transaction,
+    pub account: Option<Arc<account::Account>>,
+    pub category: Option<Arc<category::Category>>,
+    pub parent_category: Option<Arc<category::Category>>,
+    pub tags: Vec<Arc<tag::Tag>>,
+}
+
+/// Resources looked up while expanding transactions. Accounts and categories
+/// are fetched with a single list request the first time one is missing, so
+/// expanding a page of transactions only costs a handful of requests.
+#[derive(Default)]
+pub struct Cache {
+    accounts: Mutex<Option<HashMap<account::AccountId, Arc<account::Account>>>>,
+    categories: Mutex<Option<HashMap<category::CategoryId, Arc<category::Category>>>>,
+}
+
+impl Cache {
+    pub fn clear(&self) {
+        *self.accounts.lock().expect("account cache poisoned") = None;
+        *self.categories.lock().expect("category cache poisoned") = None;
+    }
+
+    fn account(
+        &self,
+        client: &Client,
+        id: &account::AccountId,
+    ) -> error::Result<Arc<account::Account>> {
+        let mut accounts = self.accounts.lock().expect("account cache poisoned");
+        if accounts.is_none() {
+            debug!("Priming account cache");
+            let listed = ok_or_upbank_error(client.account.list().exec()?)?;
+            *accounts = Some(
+                listed
+                    .data
+                    .into_iter()
+                    .map(|a| (a.id.clone(), Arc::new(a)))
+                    .collect(),
+            );
+        }
+        let accounts = accounts.get_or_insert_with(HashMap::new);
+        if let Some(account) = accounts.get(id) {
+            return Ok(account.clone());
+        }
+        let account = Arc::new(ok_or_upbank_error(client.account.get(id)?)?.data);
+        accounts.insert(id.clone(), account.clone());
+        Ok(account)
+    }
+
+    fn category(
+        &self,
+        client: &Client,
+        id: &category::CategoryId,
+    ) -> error::Result<Arc<category::Category>> {
+        let mut categories = self.categories.lock().expect("category cache poisoned");
+        if categories.is_none() {
+            debug!("Priming category cache");
+            let listed = ok_or_upbank_error(client.category.list().exec()?)?;
+            *categories = Some(
+                listed
+                    .data
+                    .into_iter()
+                    .map(|c| (c.id.clone(), Arc::new(c)))
+                    .collect(),
+            );
+        }
+        let categories = categories.get_or_insert_with(HashMap::new);
+        if let Some(category) = categories.get(id) {
+            return Ok(category.clone());
+        }
+        let category = Arc::new(ok_or_upbank_error(client.category.get(id)?)?.data);
+        categories.insert(id.clone(), category.clone());
+        Ok(category)
+    }
+}
+
+fn ok_or_upbank_error<T>(
+    resp: response::Response<T>,
+) -> error::Result<response::SuccessfulResponse<T>> {
+    match resp {
+        response::Response::Ok(resp) => Ok(resp),
+        response::Response::Err(e) => Err(error::ClientError::UpBankError(e)),
+    }
+}
+
+pub(crate) fn expand(
+    client: &Client,
+    transaction: transaction::Transaction,
+) -> error::Result<ExpandedTransaction> {
+    let cache = &client.cache;
+    let account = match transaction.account_id() {
+        Some(id) => Some(cache.account(client, id)?),
+        None => None,
+    };
+    let category = match transaction.category_id() {
+        Some(id) => Some(cache.category(client, id)?),
+        None => None,
+    };
+    let parent_category = match transaction.parent_category_id() {
+        Some(id) => Some(cache.category(client, id)?),
+        None => None,
+    };
+    // UpBank can't retrieve a single tag, and a tag is only its ID anyway,
+    // so tags are built from the relationship without a request.
+    let tags = transaction
+        .relationships
+        .tags
+        .data
+        .iter()
+        .map(|tag| {
+            Arc::new(tag::Tag {
+                resource_type: tag.resource_type,
+                id: tag.id.clone(),
+                relationships: tag::Relationships { transactions: None },
+            })
+        })
+        .collect();
+    Ok(ExpandedTransaction {
+        transaction,
+        account,
+        category,
+        parent_category,
+        tags,
+    })
+}
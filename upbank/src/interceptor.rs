@@ -0,0 +1,289 @@
+//! Hooks that see every request the client sends and every response it gets
+//! back, for logging, tracing and metrics.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use url::Url;
+
+/// An outgoing request.
+#[derive(Debug, Clone)]
+pub struct RequestInfo {
+    /// Unique (per client) ID of the request, to tie it to its response.
+    pub id: u64,
+    pub method: reqwest::Method,
+    pub url: Url,
+    pub body_size: Option<u64>,
+}
+
+/// The outcome of a request.
+#[derive(Debug, Clone)]
+pub struct ResponseInfo {
+    /// Status of the response, or `None` if no response was received.
+    pub status: Option<reqwest::StatusCode>,
+    pub latency: Duration,
+    /// Size of the response body, if UpBank said what it was.
+    pub body_size: Option<u64>,
+    /// Why no response was received.
+    pub error: Option<String>,
+}
+
+impl ResponseInfo {
+    /// The request failed to send or UpBank responded with an error.
+    pub fn is_failure(&self) -> bool {
+        !matches!(self.status, Some(status) if status.is_success())
+    }
+}
+
+/// Registered on a `Client` with `Client::add_interceptor`. Interceptors are
+/// called in the order they were added, on whichever thread sent the
+/// request.
+pub trait Interceptor: Send + Sync {
+    fn on_request(&self, _request: &RequestInfo) {}
+
+    fn on_response(&self, _request: &RequestInfo, _response: &ResponseInfo) {}
+}
+
+/// The interceptors shared by every endpoint of a client.
+#[derive(Default)]
+pub(crate) struct Chain {
+    interceptors: RwLock<Vec<Arc<dyn Interceptor>>>,
+    next_id: AtomicU64,
+}
+
+impl Chain {
+    pub(crate) fn add(&self, interceptor: Arc<dyn Interceptor>) {
+        self.interceptors
+            .write()
+            .expect("interceptors poisoned")
+            .push(interceptor);
+    }
+
+    pub(crate) fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub(crate) fn on_request(&self, request: &RequestInfo) {
+        for interceptor in self
+            .interceptors
+            .read()
+            .expect("interceptors poisoned")
+            .iter()
+        {
+            interceptor.on_request(request);
+        }
+    }
+
+    pub(crate) fn on_response(&self, request: &RequestInfo, response: &ResponseInfo) {
+        for interceptor in self
+            .interceptors
+            .read()
+            .expect("interceptors poisoned")
+            .iter()
+        {
+            interceptor.on_response(request, response);
+        }
+    }
+}
+
+/// Wraps each request in an `upbank_request` span with the method, URL,
+/// status and latency recorded on it.
+#[derive(Default)]
+pub struct TracingInterceptor {
+    spans: Mutex<HashMap<u64, tracing::Span>>,
+}
+
+impl Interceptor for TracingInterceptor {
+    fn on_request(&self, request: &RequestInfo) {
+        let span = tracing::info_span!(
+            "upbank_request",
+            id = request.id,
+            method = %request.method,
+            url = %request.url,
+            status = tracing::field::Empty,
+            latency_ms = tracing::field::Empty,
+        );
+        span.in_scope(|| tracing::debug!(body_size = ?request.body_size, "sending request"));
+        self.spans
+            .lock()
+            .expect("spans poisoned")
+            .insert(request.id, span);
+    }
+
+    fn on_response(&self, request: &RequestInfo, response: &ResponseInfo) {
+        let span = match self
+            .spans
+            .lock()
+            .expect("spans poisoned")
+            .remove(&request.id)
+        {
+            Some(span) => span,
+            None => return,
+        };
+        if let Some(status) = response.status {
+            span.record("status", status.as_u16());
+        }
+        span.record("latency_ms", response.latency.as_millis() as u64);
+        span.in_scope(|| match &response.error {
+            Some(error) => tracing::warn!(error = %error, "request failed"),
+            None => tracing::debug!(body_size = ?response.body_size, "received response"),
+        });
+    }
+}
+
+/// Upper bounds (inclusive, in milliseconds) of the latency histogram
+/// buckets. Slower requests go in a final overflow bucket.
+pub const LATENCY_BUCKETS_MS: [u64; 10] = [10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000];
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LatencyHistogram {
+    /// Count per bucket in `LATENCY_BUCKETS_MS`, plus the overflow bucket.
+    pub counts: [u64; LATENCY_BUCKETS_MS.len() + 1],
+    pub count: u64,
+    pub sum: Duration,
+}
+
+impl LatencyHistogram {
+    pub fn observe(&mut self, latency: Duration) {
+        let ms = latency.as_millis() as u64;
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| ms <= *bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.counts[bucket] += 1;
+        self.count += 1;
+        self.sum += latency;
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(self.sum / self.count as u32)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct EndpointMetrics {
+    pub requests: u64,
+    /// Requests that failed to send or got an error response.
+    pub failures: u64,
+    pub latency: LatencyHistogram,
+}
+
+/// Counts requests, failures and latency per endpoint, keyed by
+/// `endpoint_key`.
+#[derive(Default)]
+pub struct MetricsInterceptor {
+    metrics: Mutex<BTreeMap<String, EndpointMetrics>>,
+}
+
+impl MetricsInterceptor {
+    pub fn snapshot(&self) -> BTreeMap<String, EndpointMetrics> {
+        self.metrics.lock().expect("metrics poisoned").clone()
+    }
+
+    pub fn reset(&self) {
+        self.metrics.lock().expect("metrics poisoned").clear();
+    }
+}
+
+impl Interceptor for MetricsInterceptor {
+    fn on_response(&self, request: &RequestInfo, response: &ResponseInfo) {
+        let key = endpoint_key(&request.method, &request.url);
+        let mut metrics = self.metrics.lock().expect("metrics poisoned");
+        let endpoint = metrics.entry(key).or_default();
+        endpoint.requests += 1;
+        if response.is_failure() {
+            endpoint.failures += 1;
+        }
+        endpoint.latency.observe(response.latency);
+    }
+}
+
+/// Path segments that name an endpoint rather than a resource.
+const STATIC_SEGMENTS: &[&str] = &[
+    "api",
+    "v1",
+    "accounts",
+    "attachments",
+    "categories",
+    "category",
+    "logs",
+    "ping",
+    "relationships",
+    "tags",
+    "transactions",
+    "util",
+    "webhooks",
+];
+
+/// The method and path of the request with IDs replaced by `{id}`, e.g.
+/// "GET /api/v1/accounts/{id}/transactions".
+pub fn endpoint_key(method: &reqwest::Method, url: &Url) -> String {
+    let path: Vec<&str> = url
+        .path_segments()
+        .into_iter()
+        .flatten()
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            if STATIC_SEGMENTS.contains(&s) {
+                s
+            } else {
+                "{id}"
+            }
+        })
+        .collect();
+    format!("{} /{}", method, path.join("/"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{endpoint_key, LatencyHistogram};
+    use std::time::Duration;
+    use url::Url;
+
+    #[test]
+    fn test_endpoint_key() {
+        let key = |method, url| endpoint_key(&method, &Url::parse(url).unwrap());
+        assert_eq!(
+            key(
+                reqwest::Method::GET,
+                "https://api.up.com.au/api/v1/accounts/139ed96d/transactions?page%5Bsize%5D=10"
+            ),
+            "GET /api/v1/accounts/{id}/transactions"
+        );
+        assert_eq!(
+            key(
+                reqwest::Method::POST,
+                "https://api.up.com.au/api/v1/transactions/75d3cffa/relationships/tags"
+            ),
+            "POST /api/v1/transactions/{id}/relationships/tags"
+        );
+        assert_eq!(
+            key(
+                reqwest::Method::GET,
+                "https://api.up.com.au/api/v1/util/ping"
+            ),
+            "GET /api/v1/util/ping"
+        );
+    }
+
+    #[test]
+    fn test_latency_histogram() {
+        let mut histogram = LatencyHistogram::default();
+        assert_eq!(histogram.mean(), None);
+        histogram.observe(Duration::from_millis(5));
+        histogram.observe(Duration::from_millis(25));
+        histogram.observe(Duration::from_millis(30));
+        histogram.observe(Duration::from_secs(60));
+        assert_eq!(histogram.counts[0], 1);
+        assert_eq!(histogram.counts[1], 1);
+        assert_eq!(histogram.counts[2], 1);
+        assert_eq!(histogram.counts[10], 1);
+        assert_eq!(histogram.count, 4);
+        assert_eq!(histogram.mean(), Some(Duration::from_millis(15015)));
+    }
+}
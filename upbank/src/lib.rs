@@ -1,6 +1,15 @@
+use log::*;
+use serde::de::DeserializeOwned;
 use url::Url;
 
+pub mod endpoint;
+pub mod interceptor;
+pub mod resource;
 pub mod response;
+pub mod secret;
+
+pub use endpoint::ResourceClient;
+pub use secret::Token;
 
 // Resources
 pub mod account;
@@ -9,12 +18,13 @@ pub mod util;
 pub mod webhook;
 pub mod category;
 pub mod tag;
+pub mod attachment;
+pub mod expand;
 
 // Utilities that we share between modules but don't expose.
 mod currency;
 mod error;
 mod iso4217;
-mod resource;
 #[macro_use]
 mod macros;
 
@@ -26,8 +36,7 @@ pub fn default_base_url() -> Url {
 }
 
 pub struct Client {
-    _base_url: Url,
-    _token: String,
+    endpoint: endpoint::Endpoint,
 
     pub util: util::Util,
     pub account: account::AccountClient,
@@ -35,54 +44,96 @@ pub struct Client {
     pub webhook: webhook::WebhookClient,
     pub category: category::CategoryClient,
     pub tag: tag::TagClient,
+    pub attachment: attachment::AttachmentClient,
+
+    cache: expand::Cache,
 }
 
+// Paths have to end in a slash otherwise they get stomped by subsequent joins.
 macro_rules! client {
-    ($typ:ty, $base:expr, $path:expr, $tok:expr) => {
-        <$typ>::new(
-            $base.join($path).unwrap_or_else(|_| panic!("Couldn't add '{}' to base URL {}", $path, $base)),
-            $tok.clone(),
-        );
+    ($typ:ty, $endpoint:expr, $path:expr) => {
+        <$typ>::from_endpoint($endpoint.join($path).unwrap_or_else(|_| {
+            panic!("Couldn't add '{}' to base URL {}", $path, $endpoint.base_url())
+        }))
     };
 }
 
 impl Client {
-    pub fn new(base_url: Url, token: String) -> Self {
+    pub fn new<T: Into<secret::Token>>(base_url: Url, token: T) -> Self {
+        // Every resource client shares the root endpoint's connection pool
+        // and interceptors.
+        let endpoint = endpoint::Endpoint::new(base_url, token);
         Client {
-            _base_url: base_url.clone(),
-            _token: token.clone(),
-
-            util: util::Util::new(
-                base_url
-                    // Has to end in a slash otherwise it gets stomped by subsequent joins
-                    .join("util/")
-                    .unwrap_or_else(|_| panic!("Couldn't add 'util/' to base URL {}", base_url)),
-                token.clone(),
-            ),
-            account: account::AccountClient::new(
-                base_url.join("accounts/").unwrap_or_else(|_| {
-                    panic!("Couldn't add 'accounts/' to base URL {}", base_url)
-                }),
-                token.clone(),
-            ),
-            transaction: transaction::TransactionClient::new(
-                base_url.join("transactions/").unwrap_or_else(|_| {
-                    panic!("Couldn't add 'transactions/' to base URL {}", base_url)
-                }),
-                token.clone(),
-            ),
-            webhook: client!(webhook::WebhookClient, base_url, "webhooks/", token),
-            category: category::CategoryClient::new(
-                base_url.join("categories/").unwrap_or_else(|_| {
-                    panic!("Couldn't add 'categories/' to base URL {}", base_url)
-                }),
-                token.clone(),
-            ),
-            tag: client!(tag::TagClient, base_url, "tags/", token),
+            util: client!(util::Util, endpoint, "util/"),
+            account: client!(account::AccountClient, endpoint, "accounts/"),
+            transaction: client!(transaction::TransactionClient, endpoint, "transactions/"),
+            webhook: client!(webhook::WebhookClient, endpoint, "webhooks/"),
+            category: client!(category::CategoryClient, endpoint, "categories/"),
+            tag: client!(tag::TagClient, endpoint, "tags/"),
+            attachment: client!(attachment::AttachmentClient, endpoint, "attachments/"),
+
+            endpoint,
+            cache: expand::Cache::default(),
+        }
+    }
+
+    /// Register an interceptor to see every request sent by this client
+    /// (including its resource clients) and every response received.
+    pub fn add_interceptor(&self, interceptor: std::sync::Arc<dyn interceptor::Interceptor>) {
+        self.endpoint.add_interceptor(interceptor);
+    }
+
+    /// Resolve the account, categories and tags the transaction refers to.
+    /// Lookups are cached on the client so expanding many transactions
+    /// doesn't refetch the same resources.
+    pub fn expand_transaction(
+        &self,
+        transaction: transaction::Transaction,
+    ) -> error::Result<expand::ExpandedTransaction> {
+        expand::expand(self, transaction)
+    }
+
+    pub fn expand_transactions(
+        &self,
+        transactions: Vec<transaction::Transaction>,
+    ) -> error::Result<Vec<expand::ExpandedTransaction>> {
+        transactions
+            .into_iter()
+            .map(|t| self.expand_transaction(t))
+            .collect()
+    }
+
+    /// Fetch the resource at `link`, e.g. a `related` or `self` link taken
+    /// from another resource. The link must be under the base URL so the
+    /// token is never sent anywhere else.
+    pub fn follow<T: DeserializeOwned + std::fmt::Debug>(
+        &self,
+        link: &str,
+    ) -> error::Result<response::Response<T>> {
+        let url = Url::parse(link)?;
+        let base_url = self.endpoint.base_url();
+        if !url.as_str().starts_with(base_url.as_str()) {
+            return Err(error::ClientError::ValidationError {
+                value: link.into(),
+                reason: format!("Link is not under the base URL {}", base_url),
+            });
         }
+        debug!("Following link {}", url);
+        self.endpoint.get(url)
+    }
+
+    /// Check UpBank can be reached with the client's token. See
+    /// `util::Util::health`.
+    pub fn health(&self) -> util::Health {
+        self.util.health()
+    }
+
+    /// Forget every resource cached by `expand_transaction`.
+    pub fn clear_cache(&self) {
+        self.cache.clear();
     }
 
-    pub fn default_client(token: String) -> Self {
+    pub fn default_client<T: Into<secret::Token>>(token: T) -> Self {
         Client::new(default_base_url(), token)
     }
 }
@@ -8,6 +8,77 @@ macro_rules! setter {
     };
 }
 
+#[macro_export]
+macro_rules! resource_id {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(
+            serde::Deserialize,
+            serde::Serialize,
+            Debug,
+            Clone,
+            PartialEq,
+            Eq,
+            Hash,
+            PartialOrd,
+            Ord,
+        )]
+        #[serde(transparent)]
+        pub struct $name(String);
+
+        impl $name {
+            pub fn new<S: Into<String>>(id: S) -> Self {
+                $name(id.into())
+            }
+
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            pub fn into_inner(self) -> String {
+                self.0
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = std::convert::Infallible;
+
+            fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+                Ok($name(s.to_string()))
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(id: String) -> Self {
+                $name(id)
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(id: &str) -> Self {
+                $name(id.to_string())
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl std::borrow::Borrow<str> for $name {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+    };
+}
 
 #[cfg(test)]
 #[macro_export]
@@ -15,12 +86,70 @@ macro_rules! test_deserialization {
     ($name:ident, $file_name:expr, $type:ty) => {
         #[test]
         fn $name() {
-            let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
-            let mut path = std::path::PathBuf::from(manifest_dir);
-            path.push("data");
-            path.push($file_name);
-            let contents = std::fs::read_to_string(path).unwrap();
-            let _ = serde_json::from_str::<SuccessfulResponse<$type>>(&contents).unwrap();
+            let _: SuccessfulResponse<$type> = $crate::macros::fixture($file_name);
+        }
+    };
+}
+
+#[cfg(test)]
+#[macro_export]
+macro_rules! test_round_trip {
+    ($name:ident, $file_name:expr, $type:ty) => {
+        #[test]
+        fn $name() {
+            let original: serde_json::Value = $crate::macros::fixture($file_name);
+            let parsed: SuccessfulResponse<$type> = $crate::macros::fixture($file_name);
+            let serialized = serde_json::to_value(&parsed).unwrap();
+            $crate::macros::assert_same_shape(&original, &serialized, "$");
+            let reparsed = serde_json::from_value::<SuccessfulResponse<$type>>(serialized).unwrap();
+            assert_eq!(parsed, reparsed);
         }
     };
-}
\ No newline at end of file
+}
+
+/// Parse the file in the data directory as a `T`.
+#[cfg(test)]
+pub(crate) fn fixture<T: serde::de::DeserializeOwned>(file: &str) -> T {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let mut path = std::path::PathBuf::from(manifest_dir);
+    path.push("data");
+    path.push(file);
+    let contents = std::fs::read_to_string(path).unwrap();
+    serde_json::from_str(&contents).unwrap_or_else(|e| panic!("Failed to parse {}: {}", file, e))
+}
+
+/// Check the two documents have the same keys and array lengths all the way
+/// down and that their leaves are the same kind of value. The leaves
+/// themselves can differ, e.g. timestamps are serialized in UTC.
+#[cfg(test)]
+pub(crate) fn assert_same_shape(
+    expected: &serde_json::Value,
+    actual: &serde_json::Value,
+    path: &str,
+) {
+    use serde_json::Value;
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            let expected_keys: Vec<&String> = expected.keys().collect();
+            let actual_keys: Vec<&String> = actual.keys().collect();
+            assert_eq!(expected_keys, actual_keys, "keys differ at {}", path);
+            for (key, value) in expected {
+                assert_same_shape(value, &actual[key], &format!("{}.{}", path, key));
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            assert_eq!(expected.len(), actual.len(), "lengths differ at {}", path);
+            for (i, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+                assert_same_shape(expected, actual, &format!("{}[{}]", path, i));
+            }
+        }
+        (expected, actual) => assert_eq!(
+            std::mem::discriminant(expected),
+            std::mem::discriminant(actual),
+            "expected {} but got {} at {}",
+            expected,
+            actual,
+            path
+        ),
+    }
+}
@@ -1,6 +1,10 @@
+//! The JSON:API building blocks every UpBank resource is made of.
+
+use crate::{error, response, Client};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(rename_all = "kebab-case")]
 pub enum ResourceType {
     Transactions,
@@ -10,23 +14,143 @@ pub enum ResourceType {
     Tags,
     WebhookEvents,
     WebhookDeliveryLogs,
+    Attachments,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-pub struct Resource<A, R> {
+pub struct Resource<A, R, I = String> {
     #[serde(rename = "type")]
     pub resource_type: ResourceType,
-    pub id: String,
+    #[serde(bound(deserialize = "I: Deserialize<'de>"))]
+    pub id: I,
     #[serde(bound(deserialize = "A: Deserialize<'de>"))]
     pub attributes: A,
     #[serde(bound(deserialize = "R: Deserialize<'de>"))]
     pub relationships: R,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub links: Option<SelfLinks>,
 }
 
-#[derive(Deserialize, Debug)]
+impl<A, R, I> Resource<A, R, I>
+where
+    A: DeserializeOwned + std::fmt::Debug,
+    R: DeserializeOwned + std::fmt::Debug,
+    I: DeserializeOwned + std::fmt::Debug + ToString,
+{
+    /// Fetch the latest version of this resource through its `self` link.
+    pub fn refresh(&self, client: &Client) -> error::Result<response::Response<Self>> {
+        let links = self
+            .links
+            .as_ref()
+            .ok_or_else(|| error::ClientError::ValidationError {
+                value: self.id.to_string(),
+                reason: "Resource has no self link to refresh from".into(),
+            })?;
+        client.follow(&links.self_)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct SelfLinks {
     #[serde(rename = "self")]
     pub self_: String,
 }
+
+/// The type and ID of a resource, used to point at it from a relationship.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ResourceIdentifier<I = String> {
+    #[serde(rename = "type")]
+    pub resource_type: ResourceType,
+    #[serde(bound(deserialize = "I: Deserialize<'de>"))]
+    pub id: I,
+}
+
+/// Links of a relationship. Depending on the relationship UpBank provides a
+/// `related` link to fetch the related resources, a `self` link to modify
+/// the relationship, or neither.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct RelationshipLinks {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub related: Option<String>,
+    #[serde(rename = "self", skip_serializing_if = "Option::is_none")]
+    pub self_: Option<String>,
+}
+
+/// Relationship to at most one other resource.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ToOne<I = String> {
+    #[serde(bound(deserialize = "I: Deserialize<'de>"))]
+    pub data: Option<ResourceIdentifier<I>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<RelationshipLinks>,
+}
+
+/// Relationship to any number of other resources.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ToMany<I = String> {
+    #[serde(default = "Vec::new", bound(deserialize = "I: Deserialize<'de>"))]
+    pub data: Vec<ResourceIdentifier<I>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<RelationshipLinks>,
+}
+
+/// Relationship that only links to the related resources, e.g. an
+/// account's transactions which are too many to include.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct LinksOnly {
+    pub links: Option<RelationshipLinks>,
+}
+
+/// A relationship with the type of its IDs erased, so relationships can be
+/// inspected without knowing which resource they belong to.
+#[derive(Debug)]
+pub struct RelationshipRef<'a> {
+    pub name: &'static str,
+    pub data: Vec<ResourceIdentifier<&'a str>>,
+    pub links: Option<&'a RelationshipLinks>,
+}
+
+/// Implemented by the relationships of every resource.
+pub trait Related {
+    fn relationships(&self) -> Vec<RelationshipRef<'_>>;
+}
+
+impl<I: AsRef<str>> ResourceIdentifier<I> {
+    fn as_str_id(&self) -> ResourceIdentifier<&str> {
+        ResourceIdentifier {
+            resource_type: self.resource_type,
+            id: self.id.as_ref(),
+        }
+    }
+}
+
+impl<I: AsRef<str>> ToOne<I> {
+    pub fn to_ref(&self, name: &'static str) -> RelationshipRef<'_> {
+        RelationshipRef {
+            name,
+            data: self.data.iter().map(|d| d.as_str_id()).collect(),
+            links: self.links.as_ref(),
+        }
+    }
+}
+
+impl<I: AsRef<str>> ToMany<I> {
+    pub fn to_ref(&self, name: &'static str) -> RelationshipRef<'_> {
+        RelationshipRef {
+            name,
+            data: self.data.iter().map(|d| d.as_str_id()).collect(),
+            links: self.links.as_ref(),
+        }
+    }
+}
+
+impl LinksOnly {
+    pub fn to_ref(&self, name: &'static str) -> RelationshipRef<'_> {
+        RelationshipRef {
+            name,
+            data: vec![],
+            links: self.links.as_ref(),
+        }
+    }
+}
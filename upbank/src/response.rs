@@ -1,20 +1,21 @@
 use crate::error;
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct Links {
     pub prev: Option<String>,
     pub next: Option<String>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct SuccessfulResponse<T> {
     #[serde(bound(deserialize = "T: Deserialize<'de>"))]
     pub data: T,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub links: Option<Links>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Response<T> {
     Ok(SuccessfulResponse<T>),
     Err(error::Error),
@@ -42,3 +43,15 @@ impl<'de, T: Deserialize<'de>> Deserialize<'de> for Response<T> {
         Ok(Response::Ok(succ))
     }
 }
+
+impl<T: Serialize> Serialize for Response<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Response::Ok(succ) => succ.serialize(serializer),
+            Response::Err(err) => err.serialize(serializer),
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! Keeping the bearer token out of logs and memory dumps.
+
+use std::sync::Arc;
+use zeroize::Zeroizing;
+
+/// Bearer token used to authenticate with UpBank. It's redacted when
+/// formatted and wiped from memory once the last clone is dropped.
+#[derive(Clone)]
+pub struct Token(Arc<Zeroizing<String>>);
+
+impl Token {
+    pub fn new<S: Into<String>>(token: S) -> Self {
+        Token(Arc::new(Zeroizing::new(token.into())))
+    }
+
+    /// The token itself. Take care not to log it.
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Token(<redacted>)")
+    }
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "<redacted>")
+    }
+}
+
+impl From<String> for Token {
+    fn from(token: String) -> Self {
+        Token::new(token)
+    }
+}
+
+impl From<&str> for Token {
+    fn from(token: &str) -> Self {
+        Token::new(token)
+    }
+}
+
+impl std::str::FromStr for Token {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Token::new(s))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Token;
+    use crate::endpoint::Endpoint;
+
+    #[test]
+    fn test_token_is_redacted() {
+        let token = Token::new("up:yeah:secret");
+        assert_eq!(format!("{:?}", token), "Token(<redacted>)");
+        assert_eq!(token.to_string(), "<redacted>");
+        assert_eq!(token.expose_secret(), "up:yeah:secret");
+    }
+
+    #[test]
+    fn test_authorization_header_is_redacted() {
+        let url = url::Url::parse("https://api.up.com.au/api/v1/").unwrap();
+        let endpoint = Endpoint::new(url.clone(), "up:yeah:secret");
+        let req = endpoint.request(reqwest::Method::GET, url).build().unwrap();
+        let auth = &req.headers()[reqwest::header::AUTHORIZATION];
+        assert!(auth.is_sensitive());
+        assert_eq!(auth.to_str().unwrap(), "Bearer up:yeah:secret");
+        assert!(!format!("{:?}", req).contains("secret"));
+    }
+}
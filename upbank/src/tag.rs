@@ -1,105 +1,74 @@
-use crate::{error::Result, resource::ResourceType, response::Response, setter};
-use log::*;
-use serde::Deserialize;
+use crate::{
+    endpoint::{Endpoint, ListRequestBuilder, ResourceClient},
+    resource::{self, ResourceType},
+    resource_id, transaction,
+};
+use serde::{Deserialize, Serialize};
 use url::Url;
 
+resource_id!(
+    /// ID of a tag, which is the tag's label.
+    TagId
+);
+
 pub struct Client {
-    client: reqwest::blocking::Client,
-    base_url: Url,
-    token: String,
+    endpoint: Endpoint,
 }
 
 impl Client {
-    pub fn new(base_url: Url, token: String) -> Self {
-        Self {
-            client: reqwest::blocking::Client::new(),
-            base_url,
-            token,
-        }
+    pub fn new<T: Into<crate::secret::Token>>(base_url: Url, token: T) -> Self {
+        Self::from_endpoint(Endpoint::new(base_url, token))
     }
-}
 
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-pub struct RelatedLinks {
-    pub related: String,
+    pub fn from_endpoint(endpoint: Endpoint) -> Self {
+        Self { endpoint }
+    }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-pub struct LinkRelationship {
-    pub links: RelatedLinks,
+pub struct Relationships {
+    pub transactions: Option<resource::LinksOnly>,
 }
 
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-pub struct Relationships {
-    pub transactions: Option<LinkRelationship>,
+impl resource::Related for Relationships {
+    fn relationships(&self) -> Vec<resource::RelationshipRef<'_>> {
+        self.transactions
+            .iter()
+            .map(|t| t.to_ref("transactions"))
+            .collect()
+    }
 }
 
 // pub type Tag = Resource<(), Relationships>;
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Tag {
     #[serde(rename = "type")]
     pub resource_type: ResourceType,
-    pub id: String,
+    pub id: TagId,
     pub relationships: Relationships,
 }
 
 pub type TagClient = Client;
 
 impl TagClient {
-    pub fn get(&self, id: &str) -> Result<Response<Tag>> {
-        let url = self.base_url.join(id)?;
-        debug!("Sending get tag request to {}", url.to_string());
-        let resp = self
-            .client
-            .get(url)
-            .bearer_auth(&self.token)
-            .send()?
-            .json::<Response<Tag>>()?;
-        trace!("Response from get tag request: {:?}", resp);
-        Ok(resp)
-    }
-
-    pub fn list(&self) -> ListRequestBuilder {
-        ListRequestBuilder {
-            size: None,
-            base_url: self.base_url.clone(),
-            client: &self.client,
-            token: self.token.clone(),
-        }
+    /// List the transactions tagged with `id`. The other transaction filters
+    /// can still be set on the returned builder.
+    pub fn transactions(&self, id: &TagId) -> transaction::ListRequestBuilder {
+        // Tagged transactions are listed through the transactions endpoint.
+        let mut req = ListRequestBuilder::new(&self.endpoint, "../transactions/");
+        req.tag(id.clone());
+        req
     }
 }
 
-pub struct ListRequestBuilder<'a> {
-    size: Option<u32>,
+impl ResourceClient<Tag> for TagClient {
+    type Id = TagId;
 
-    base_url: Url,
-    client: &'a reqwest::blocking::Client,
-    token: String,
-}
-
-impl<'a> ListRequestBuilder<'a> {
-    setter!(size, u32);
-
-    pub fn exec(&self) -> Result<Response<Vec<Tag>>> {
-        let mut query = vec![];
-        if let Some(size) = self.size {
-            query.push(("page[size]", size));
-        }
-        debug!("Sending list tag request to {}", self.base_url.to_string());
-        let resp = self
-            .client
-            .get(self.base_url.clone())
-            .bearer_auth(&self.token)
-            .query(&query)
-            .send()?
-            .json::<Response<Vec<Tag>>>()?;
-        trace!("Response from list tag request: {:?}", resp);
-        Ok(resp)
+    fn endpoint(&self) -> &Endpoint {
+        &self.endpoint
     }
 }
 
@@ -107,7 +76,18 @@ impl<'a> ListRequestBuilder<'a> {
 mod test {
     use super::Tag;
     use crate::response::SuccessfulResponse;
-    use crate::test_deserialization;
+    use crate::{test_deserialization, test_round_trip};
 
     test_deserialization!(test_multi_de, "tag_list.json", Vec<Tag>);
+    test_round_trip!(test_multi_round_trip, "tag_list.json", Vec<Tag>);
+
+    #[test]
+    fn test_transactions_to_url() {
+        let client = crate::Client::default_client("token".to_string());
+        let req = client.tag.transactions(&"Holiday".into());
+        assert_eq!(
+            req.to_url().unwrap().as_str(),
+            "https://api.up.com.au/api/v1/transactions/?filter%5Btag%5D=Holiday"
+        );
+    }
 }
@@ -1,14 +1,18 @@
-use crate::{currency, error, resource, response, setter};
+use crate::endpoint::{self, ResourceClient};
+use crate::{account, category, currency, error, resource, resource_id, response, tag};
 use log::*;
-use serde::ser::{SerializeTuple, Serializer};
 use serde::{Deserialize, Serialize};
 use strum_macros::Display;
 use url::Url;
 
+resource_id!(
+    /// ID of a transaction.
+    TransactionId
+);
+
 pub struct TransactionClient {
-    client: reqwest::blocking::Client,
-    base_url: Url,
-    token: String,
+    endpoint: endpoint::Endpoint,
+    categories: category::CategoryClient,
 }
 
 #[derive(Deserialize, Debug, Display, PartialEq, Serialize, Clone)]
@@ -32,28 +36,28 @@ impl std::str::FromStr for Status {
     }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct HoldInfo {
     pub amount: currency::Money,
     pub foreign_amount: Option<currency::Money>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct RoundUp {
     pub amount: currency::Money,
     pub boost_portion: Option<currency::Money>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Cashback {
     pub description: String,
     pub amount: currency::Money,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Attributes {
     pub status: Status,
@@ -69,241 +73,427 @@ pub struct Attributes {
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-pub struct RelatedLinks {
-    pub related: Option<String>,
+pub struct Relationships {
+    pub account: resource::ToOne<account::AccountId>,
+    pub category: resource::ToOne<category::CategoryId>,
+    pub parent_category: resource::ToOne<category::CategoryId>,
+    pub tags: resource::ToMany<tag::TagId>,
 }
 
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-pub struct RelationshipData {
-    #[serde(rename = "type")]
-    pub type_: String,
-    pub id: String,
+impl resource::Related for Relationships {
+    fn relationships(&self) -> Vec<resource::RelationshipRef<'_>> {
+        vec![
+            self.account.to_ref("account"),
+            self.category.to_ref("category"),
+            self.parent_category.to_ref("parentCategory"),
+            self.tags.to_ref("tags"),
+        ]
+    }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-pub struct Relationship {
-    pub data: Option<RelationshipData>,
-    pub links: Option<RelatedLinks>,
+pub struct TagInputResources {
+    pub data: Vec<resource::ResourceIdentifier<tag::TagId>>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-pub struct TagsRelationship {
-    pub data: Vec<RelationshipData>,
-    pub links: resource::SelfLinks,
+pub struct CategoryInput {
+    pub data: Option<resource::ResourceIdentifier<category::CategoryId>>,
 }
 
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-pub struct Relationships {
-    pub account: Relationship,
-    pub category: Relationship,
-    pub parent_category: Relationship,
-    pub tags: TagsRelationship,
+impl CategoryInput {
+    /// The body that sets a transaction's category, or clears it if
+    /// `category` is `None`.
+    pub fn new(category: Option<category::CategoryId>) -> Self {
+        CategoryInput {
+            data: category.map(|c| resource::ResourceIdentifier {
+                resource_type: resource::ResourceType::Categories,
+                id: c,
+            }),
+        }
+    }
 }
 
-#[derive(Serialize, Debug)]
-#[serde(rename_all = "camelCase")]
-pub struct TagInputResource {
-    #[serde(rename = "type")]
-    pub resource_type: resource::ResourceType,
-    pub id: String,
-}
+pub type Transaction = resource::Resource<Attributes, Relationships, TransactionId>;
 
-#[derive(Serialize, Debug)]
-#[serde(rename_all = "camelCase")]
-pub struct TagInputResources {
-    pub data: Vec<TagInputResource>,
-}
+impl Transaction {
+    /// Money left the account.
+    pub fn is_debit(&self) -> bool {
+        self.attributes.amount.value_in_base_units < 0
+    }
 
-pub type Transaction = resource::Resource<Attributes, Relationships>;
+    /// Money came into the account.
+    pub fn is_credit(&self) -> bool {
+        self.attributes.amount.value_in_base_units > 0
+    }
 
-impl TransactionClient {
-    pub fn new(base_url: Url, token: String) -> Self {
-        TransactionClient {
-            client: reqwest::blocking::Client::new(),
-            base_url,
-            token,
+    /// The settled amount, or the held amount while the transaction is
+    /// still held.
+    pub fn amount(&self) -> &currency::Money {
+        &self.attributes.amount
+    }
+
+    /// The amount when the transaction was first held, which can differ
+    /// from the settled amount.
+    pub fn held_amount(&self) -> Option<&currency::Money> {
+        self.attributes.hold_info.as_ref().map(|h| &h.amount)
+    }
+
+    /// How much the settled amount differs from the held amount, in base
+    /// units, e.g. after a tip was added. `None` until the transaction
+    /// settles or if the amounts are the same.
+    pub fn settled_difference(&self) -> Option<i64> {
+        let held = self.held_amount()?;
+        let amount = self.amount();
+        if self.attributes.status != Status::SETTLED || held.currency_code != amount.currency_code {
+            return None;
         }
+        Some(amount.value_in_base_units - held.value_in_base_units).filter(|diff| *diff != 0)
     }
 
-    pub fn list(&self) -> ListRequestBuilder {
-        ListRequestBuilder {
-            base_url: self.base_url.clone(),
-            client: &self.client,
-            token: self.token.clone(),
+    /// The amount in the currency the transaction was made in, if it wasn't
+    /// made in the account's currency.
+    pub fn foreign_amount(&self) -> Option<&currency::Money> {
+        self.attributes.foreign_amount.as_ref()
+    }
 
-            size: None,
-            status: None,
-            since: None,
-            until: None,
-            category: None,
-            tag: None,
+    /// Units of the foreign currency bought with one unit of the account's
+    /// currency, e.g. IDR per AUD.
+    pub fn fx_rate(&self) -> Option<f64> {
+        let foreign: f64 = self.foreign_amount()?.value.parse().ok()?;
+        let amount: f64 = self.amount().value.parse().ok()?;
+        if amount == 0.0 {
+            None
+        } else {
+            Some(foreign / amount)
         }
     }
 
-    pub fn get(&self, id: String) -> error::Result<response::Response<Transaction>> {
-        let url = self.base_url.join(&id)?;
-        debug!("Sending transaction get request to {}", url.to_string());
-        let resp = self
-            .client
-            .get(url)
-            .bearer_auth(&self.token)
-            .send()?
-            .json::<response::Response<Transaction>>()?;
-        trace!("Transaction get request responded with {:?}", resp);
-        Ok(resp)
+    /// The amount rounded up into a saver, including any boost.
+    pub fn round_up(&self) -> Option<&currency::Money> {
+        self.attributes.round_up.as_ref().map(|r| &r.amount)
+    }
+
+    pub fn account_id(&self) -> Option<&account::AccountId> {
+        self.relationships.account.data.as_ref().map(|d| &d.id)
+    }
+
+    pub fn category_id(&self) -> Option<&category::CategoryId> {
+        self.relationships.category.data.as_ref().map(|d| &d.id)
+    }
+
+    pub fn parent_category_id(&self) -> Option<&category::CategoryId> {
+        self.relationships
+            .parent_category
+            .data
+            .as_ref()
+            .map(|d| &d.id)
+    }
+
+    pub fn tag_ids(&self) -> Vec<&tag::TagId> {
+        self.relationships.tags.data.iter().map(|d| &d.id).collect()
     }
 
-    pub fn tag(&self, id: &str, tags: Vec<String>) -> error::Result<()> {
+    /// When the transaction settled, or when it was created if it hasn't
+    /// settled yet.
+    pub fn effective_date(&self) -> chrono::DateTime<chrono::Utc> {
+        self.attributes
+            .settled_at
+            .unwrap_or(self.attributes.created_at)
+    }
+}
+
+impl TransactionClient {
+    pub fn new<T: Into<crate::secret::Token>>(base_url: Url, token: T) -> Self {
+        Self::from_endpoint(endpoint::Endpoint::new(base_url, token))
+    }
+
+    pub fn from_endpoint(endpoint: endpoint::Endpoint) -> Self {
+        // Categories live alongside transactions so we need our own client to
+        // validate them when categorising.
+        let categories = endpoint.join("../categories/").unwrap_or_else(|_| {
+            panic!(
+                "Couldn't add '../categories/' to base URL {}",
+                endpoint.base_url()
+            )
+        });
+        TransactionClient {
+            categories: category::CategoryClient::from_endpoint(categories),
+            endpoint,
+        }
+    }
+
+    pub fn tag(&self, id: &TransactionId, tags: Vec<tag::TagId>) -> error::Result<()> {
         self.add_or_delete_tag(id, tags, false)
     }
 
-    pub fn delete_tag(&self, id: &str, tags: Vec<String>) -> error::Result<()> {
+    pub fn delete_tag(&self, id: &TransactionId, tags: Vec<tag::TagId>) -> error::Result<()> {
         self.add_or_delete_tag(id, tags, true)
     }
 
-    fn add_or_delete_tag(&self, id: &str, tags: Vec<String>, delete: bool) -> error::Result<()> {
-        let url = self.base_url.join(&format!("{}/relationships/tags", id))?;
+    /// Set the category of the transaction, or clear it if `category` is
+    /// `None`. The category is checked against the categories UpBank knows
+    /// about before the request is sent, and must be a child category.
+    pub fn categorize(
+        &self,
+        id: &TransactionId,
+        category: Option<category::CategoryId>,
+    ) -> error::Result<()> {
+        let categories = match category {
+            Some(_) => self.list_categories()?,
+            None => vec![],
+        };
+        self.categorize_with(id, category, &categories)
+    }
+
+    /// Like `categorize`, but checks the category against `categories`
+    /// rather than listing them, for callers that already have.
+    pub fn categorize_with(
+        &self,
+        id: &TransactionId,
+        category: Option<category::CategoryId>,
+        categories: &[category::Category],
+    ) -> error::Result<()> {
+        if let Some(category) = &category {
+            check_category(categories, category)?;
+        }
+        let url = self
+            .endpoint
+            .url(&format!("{}/relationships/category", id))?;
+        debug!("Categorizing transaction {} as {:?}", id, category);
+        let body = CategoryInput::new(category);
+        self.endpoint.execute(
+            self.endpoint
+                .request(reqwest::Method::PATCH, url)
+                .json(&body),
+        )
+    }
+
+    fn list_categories(&self) -> error::Result<Vec<category::Category>> {
+        match self.categories.list().exec()? {
+            response::Response::Ok(categories) => Ok(categories.data),
+            response::Response::Err(e) => Err(error::ClientError::UpBankError(e)),
+        }
+    }
+
+    fn add_or_delete_tag(
+        &self,
+        id: &TransactionId,
+        tags: Vec<tag::TagId>,
+        delete: bool,
+    ) -> error::Result<()> {
+        let url = self.endpoint.url(&format!("{}/relationships/tags", id))?;
         debug!("Tagging transaction {} with tags {:?}", id, tags);
         let body = TagInputResources {
             data: tags
                 .iter()
-                .map(|t| TagInputResource {
+                .map(|t| resource::ResourceIdentifier {
                     resource_type: resource::ResourceType::Tags,
                     id: t.clone(),
                 })
                 .collect(),
         };
-        let se_body = serde_json::to_string(&body)?;
-        let req = if delete {
-            self.client.delete(url)
+        let method = if delete {
+            reqwest::Method::DELETE
         } else {
-            self.client.post(url)
+            reqwest::Method::POST
         };
-        req.bearer_auth(&self.token).body(se_body).send()?;
-        Ok(())
+        self.endpoint
+            .execute(self.endpoint.request(method, url).json(&body))
     }
 }
 
-pub struct ListRequestBuilder<'a> {
-    base_url: Url,
-    client: &'a reqwest::blocking::Client,
-    token: String,
-
-    size: Option<u32>,
-    status: Option<Status>,
-    since: Option<chrono::DateTime<chrono::Utc>>,
-    until: Option<chrono::DateTime<chrono::Utc>>,
-    category: Option<String>,
-    tag: Option<String>,
+/// Check `id` is one of `categories` and a child category, as transactions
+/// can't be assigned to a parent category.
+fn check_category(
+    categories: &[category::Category],
+    id: &category::CategoryId,
+) -> error::Result<()> {
+    match categories.iter().find(|c| &c.id == id) {
+        None => Err(error::ClientError::ValidationError {
+            value: id.to_string(),
+            reason: "No category with this ID exists".into(),
+        }),
+        Some(c) if c.relationships.parent.data.is_none() => {
+            Err(error::ClientError::ValidationError {
+                value: id.to_string(),
+                reason: "Transactions can only be assigned to child categories".into(),
+            })
+        }
+        Some(_) => Ok(()),
+    }
 }
 
-enum ListParams {
-    Size(u32),
-    Status(Status),
-    Since(chrono::DateTime<chrono::Utc>),
-    Until(chrono::DateTime<chrono::Utc>),
-    Category(String),
-    Tag(String),
-}
+impl endpoint::ResourceClient<Transaction> for TransactionClient {
+    type Id = TransactionId;
 
-impl Serialize for ListParams {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let mut tu = serializer.serialize_tuple(2)?;
-        match self {
-            ListParams::Size(val) => {
-                tu.serialize_element("page[size]")?;
-                tu.serialize_element(val)?;
-            }
-            ListParams::Status(val) => {
-                tu.serialize_element("filter[status]")?;
-                tu.serialize_element(val)?;
-            }
-            ListParams::Since(val) => {
-                tu.serialize_element("filter[since]")?;
-                tu.serialize_element(val)?;
-            }
-            ListParams::Until(val) => {
-                tu.serialize_element("filter[until]")?;
-                tu.serialize_element(val)?;
-            }
-            ListParams::Category(val) => {
-                tu.serialize_element("filter[category]")?;
-                tu.serialize_element(val)?;
-            }
-            ListParams::Tag(val) => {
-                tu.serialize_element("filter[tag]")?;
-                tu.serialize_element(val)?;
-            }
-        };
-        tu.end()
+    fn endpoint(&self) -> &endpoint::Endpoint {
+        &self.endpoint
     }
 }
 
-impl<'a> ListRequestBuilder<'a> {
-    setter!(size, u32);
-    setter!(status, Status);
-    setter!(since, chrono::DateTime<chrono::Utc>);
-    setter!(until, chrono::DateTime<chrono::Utc>);
-    setter!(category, String);
-    setter!(tag, String);
-
-    pub fn exec(&self) -> error::Result<response::Response<Vec<Transaction>>> {
-        let url = self.base_url.clone();
-
-        let mut query = vec![];
+pub type ListRequestBuilder = endpoint::ListRequestBuilder<Transaction>;
 
-        if let Some(size) = self.size {
-            query.push(ListParams::Size(size));
-        }
-
-        if let Some(status) = &self.status {
-            query.push(ListParams::Status(status.clone()));
-        }
-
-        if let Some(since) = self.since {
-            query.push(ListParams::Since(since))
-        }
+impl ListRequestBuilder {
+    pub fn status(&mut self, status: Status) -> &mut Self {
+        self.param("filter[status]", status.to_string())
+    }
 
-        if let Some(until) = self.until {
-            query.push(ListParams::Until(until));
-        }
+    pub fn since(&mut self, since: chrono::DateTime<chrono::Utc>) -> &mut Self {
+        self.param("filter[since]", since.to_rfc3339())
+    }
 
-        if let Some(category) = &self.category {
-            query.push(ListParams::Category(category.clone()));
-        }
+    pub fn until(&mut self, until: chrono::DateTime<chrono::Utc>) -> &mut Self {
+        self.param("filter[until]", until.to_rfc3339())
+    }
 
-        if let Some(tag) = &self.tag {
-            query.push(ListParams::Tag(tag.clone()));
-        }
+    pub fn category(&mut self, category: category::CategoryId) -> &mut Self {
+        self.param("filter[category]", category.into_inner())
+    }
 
-        debug!("Sending transaction list request to {}", url.to_string(),);
-        let resp = self
-            .client
-            .get(url)
-            .bearer_auth(&self.token)
-            .query(&query)
-            .send()?
-            .json::<response::Response<Vec<Transaction>>>()?;
-        trace!("Transaction list request responded with {:?}", resp);
-        Ok(resp)
+    pub fn tag(&mut self, tag: tag::TagId) -> &mut Self {
+        self.param("filter[tag]", tag.into_inner())
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::Transaction;
+    use super::{check_category, CategoryInput, ListRequestBuilder, Status, Transaction};
+    use crate::category::Category;
+    use crate::error::ClientError;
+    use crate::macros::fixture;
+    use crate::resource::{Related, ResourceType};
     use crate::response::SuccessfulResponse;
-    use crate::test_deserialization;
+    use crate::ResourceClient;
+    use crate::{test_deserialization, test_round_trip};
 
     test_deserialization!(test_de, "transaction.json", Transaction);
     test_deserialization!(test_multi_de, "transaction_list.json", Vec<Transaction>);
+    test_round_trip!(test_round_trip, "transaction.json", Transaction);
+    test_round_trip!(
+        test_multi_round_trip,
+        "transaction_list.json",
+        Vec<Transaction>
+    );
+
+    fn transaction() -> Transaction {
+        fixture::<SuccessfulResponse<Transaction>>("transaction.json").data
+    }
+
+    #[test]
+    fn test_walk_relationships() {
+        let transaction = transaction();
+        let relationships = transaction.relationships.relationships();
+        let names: Vec<&str> = relationships.iter().map(|r| r.name).collect();
+        assert_eq!(names, vec!["account", "category", "parentCategory", "tags"]);
+        let account = &relationships[0].data[0];
+        assert_eq!(account.resource_type, ResourceType::Accounts);
+        assert_eq!(account.id, "139ed96d-9697-4c4a-b221-3d0f72d656cd");
+        assert!(relationships[1].data.is_empty());
+        assert!(relationships[3].links.unwrap().self_.is_some());
+    }
+
+    #[test]
+    fn test_settled_difference() {
+        let mut transaction = transaction();
+        assert_eq!(transaction.settled_difference(), None);
+
+        transaction.attributes.amount.value_in_base_units = -11500;
+        assert_eq!(transaction.settled_difference(), Some(-708));
+
+        transaction.attributes.status = Status::HELD;
+        assert_eq!(transaction.settled_difference(), None);
+    }
+
+    #[test]
+    fn test_list_to_url() {
+        let client = crate::Client::default_client("token".to_string());
+        let mut req = client.transaction.list();
+        req.size(10)
+            .status(Status::HELD)
+            .since("2020-07-01T00:00:00Z".parse().unwrap())
+            .tag("Holiday".into());
+        assert_eq!(
+            req.to_url().unwrap().as_str(),
+            "https://api.up.com.au/api/v1/transactions/?\
+             filter%5Bsince%5D=2020-07-01T00%3A00%3A00%2B00%3A00&\
+             filter%5Bstatus%5D=HELD&filter%5Btag%5D=Holiday&page%5Bsize%5D=10"
+        );
+
+        let account = client
+            .account
+            .transactions(&"139ed96d".into())
+            .to_url()
+            .unwrap();
+        assert_eq!(
+            account.as_str(),
+            "https://api.up.com.au/api/v1/accounts/139ed96d/transactions"
+        );
+    }
+
+    #[test]
+    fn test_builder_is_owned() {
+        fn assert_owned<T: Send + Sync + Clone + 'static>(_: &T) {}
+        let client = crate::Client::default_client("token".to_string());
+        let req: ListRequestBuilder = client.transaction.list();
+        assert_owned(&req);
+        drop(client);
+        assert!(req.to_url().is_ok());
+    }
+
+    #[test]
+    fn test_accessors() {
+        let transaction = transaction();
+        assert!(transaction.is_debit());
+        assert!(!transaction.is_credit());
+        assert_eq!(transaction.amount().value_in_base_units, -10792);
+        assert_eq!(
+            transaction.held_amount().unwrap().value_in_base_units,
+            -10792
+        );
+        assert_eq!(transaction.foreign_amount().unwrap().value, "-1053698.77");
+        let rate = transaction.fx_rate().unwrap();
+        assert!((rate - 9763.7).abs() < 0.1, "unexpected rate {}", rate);
+        assert_eq!(transaction.round_up().unwrap().value_in_base_units, -8);
+        assert_eq!(
+            transaction.account_id().unwrap().as_str(),
+            "139ed96d-9697-4c4a-b221-3d0f72d656cd"
+        );
+        assert!(transaction.category_id().is_none());
+        assert!(transaction.parent_category_id().is_none());
+        assert!(transaction.tag_ids().is_empty());
+        assert_eq!(
+            transaction.effective_date(),
+            transaction.attributes.settled_at.unwrap()
+        );
+    }
+
+    #[test]
+    fn test_category_input() {
+        let set = CategoryInput::new(Some("restaurants-and-cafes".into()));
+        assert_eq!(
+            serde_json::to_string(&set).unwrap(),
+            r#"{"data":{"type":"categories","id":"restaurants-and-cafes"}}"#
+        );
+        let clear = CategoryInput::new(None);
+        assert_eq!(serde_json::to_string(&clear).unwrap(), r#"{"data":null}"#);
+    }
+
+    #[test]
+    fn test_check_category() {
+        let categories = fixture::<SuccessfulResponse<Vec<Category>>>("category_list.json").data;
+        assert!(check_category(&categories, &"hobbies".into()).is_ok());
+        assert!(matches!(
+            check_category(&categories, &"good-life".into()),
+            Err(ClientError::ValidationError { .. })
+        ));
+        assert!(matches!(
+            check_category(&categories, &"groceries".into()),
+            Err(ClientError::ValidationError { .. })
+        ));
+    }
 }
@@ -1,27 +1,25 @@
-use crate::error;
+use crate::{endpoint, error};
 use log::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use url::Url;
 
 pub struct Util {
-    client: reqwest::blocking::Client,
-    base_url: Url,
-    token: String,
+    endpoint: endpoint::Endpoint,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Meta {
     pub id: String,
     pub status_emoji: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct Ping {
     pub meta: Meta,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum PingResponse {
     Ok(Ping),
@@ -29,27 +27,118 @@ pub enum PingResponse {
 }
 
 impl Util {
-    pub fn new(base_url: Url, token: String) -> Self {
-        Util {
-            client: reqwest::blocking::Client::new(),
-            base_url,
-            token,
-        }
+    pub fn new<T: Into<crate::secret::Token>>(base_url: Url, token: T) -> Self {
+        Self::from_endpoint(endpoint::Endpoint::new(base_url, token))
+    }
+
+    pub fn from_endpoint(endpoint: endpoint::Endpoint) -> Self {
+        Util { endpoint }
     }
 
     pub fn ping(&self) -> error::Result<PingResponse> {
-        let ping_url = self
-            .base_url
-            .join("ping")
-            .expect("could not join 'ping' to base URL");
-        debug!("Sending ping request to {}", ping_url.to_string(),);
+        let ping_url = self.endpoint.url("ping")?;
         let resp = self
-            .client
-            .get(ping_url)
-            .bearer_auth(&self.token)
-            .send()?
+            .endpoint
+            .send(self.endpoint.request(reqwest::Method::GET, ping_url))?
             .json::<PingResponse>()?;
         trace!("Ping responded with {:?}", resp);
         Ok(resp)
     }
+
+    /// Ping UpBank and report on the connection to it. Failures are reported
+    /// in the returned `Health` rather than as an error.
+    pub fn health(&self) -> Health {
+        let mut health = Health {
+            // Report the API's base URL rather than the util endpoint's.
+            base_url: self
+                .endpoint
+                .url("../")
+                .map_or_else(|_| self.endpoint.base_url().to_string(), |u| u.to_string()),
+            status: HealthStatus::Unreachable,
+            latency_ms: None,
+            clock_skew_secs: None,
+            status_emoji: None,
+            error: None,
+        };
+        let ping_url = match self.endpoint.url("ping") {
+            Ok(url) => url,
+            Err(e) => {
+                health.error = Some(e.to_string());
+                return health;
+            }
+        };
+
+        debug!("Sending health check request to {}", ping_url);
+        let sent_at = chrono::Utc::now();
+        let started = std::time::Instant::now();
+        let result = self
+            .endpoint
+            .send(self.endpoint.request(reqwest::Method::GET, ping_url));
+        let latency = started.elapsed();
+        let resp = match result {
+            Ok(resp) => resp,
+            Err(e) => {
+                health.error = Some(e.to_string());
+                return health;
+            }
+        };
+
+        health.latency_ms = Some(latency.as_millis() as u64);
+        // Assume the server stamped the response halfway through the round
+        // trip.
+        health.clock_skew_secs = resp
+            .headers()
+            .get(reqwest::header::DATE)
+            .and_then(|date| date.to_str().ok())
+            .and_then(|date| chrono::DateTime::parse_from_rfc2822(date).ok())
+            .and_then(|date| {
+                let midpoint = sent_at + chrono::Duration::from_std(latency / 2).ok()?;
+                Some((date.with_timezone(&chrono::Utc) - midpoint).num_seconds())
+            });
+        health.status = match resp.status() {
+            status if status.is_success() => HealthStatus::Healthy,
+            reqwest::StatusCode::UNAUTHORIZED => HealthStatus::InvalidToken,
+            _ => HealthStatus::ApiError,
+        };
+        match resp.json::<PingResponse>() {
+            Ok(PingResponse::Ok(ping)) => health.status_emoji = Some(ping.meta.status_emoji),
+            Ok(PingResponse::Err(e)) => health.error = Some(e.to_string().trim().to_string()),
+            Err(e) => {
+                health.status = HealthStatus::ApiError;
+                health.error = Some(e.to_string());
+            }
+        }
+        health
+    }
+}
+
+/// Outcome of a health check.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Healthy,
+    /// UpBank rejected the token (401).
+    InvalidToken,
+    /// UpBank responded with some other error.
+    ApiError,
+    /// UpBank couldn't be reached at all.
+    Unreachable,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Health {
+    pub base_url: String,
+    pub status: HealthStatus,
+    /// Round trip time of the ping, if UpBank responded.
+    pub latency_ms: Option<u64>,
+    /// How far UpBank's clock (from the `Date` header) is ahead of ours.
+    pub clock_skew_secs: Option<i64>,
+    pub status_emoji: Option<String>,
+    pub error: Option<String>,
+}
+
+impl Health {
+    pub fn is_healthy(&self) -> bool {
+        self.status == HealthStatus::Healthy
+    }
 }
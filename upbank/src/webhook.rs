@@ -1,200 +1,76 @@
-use crate::{error, resource, response, setter};
+use crate::endpoint::{self, ResourceClient};
+use crate::{error, resource, resource_id, response, transaction};
 use log::*;
-use serde::ser::{SerializeStruct, SerializeTuple, Serializer};
 use serde::{Deserialize, Serialize};
 use strum_macros::Display;
 use url::Url;
 
+resource_id!(
+    /// ID of a webhook.
+    WebhookId
+);
+
 pub struct WebhookClient {
-    client: reqwest::blocking::Client,
-    base_url: Url,
-    token: String,
+    endpoint: endpoint::Endpoint,
 }
 
 impl WebhookClient {
-    pub fn new(base_url: Url, token: String) -> Self {
-        WebhookClient {
-            client: reqwest::blocking::Client::new(),
-            base_url,
-            token,
-        }
+    pub fn new<T: Into<crate::secret::Token>>(base_url: Url, token: T) -> Self {
+        Self::from_endpoint(endpoint::Endpoint::new(base_url, token))
     }
 
-    pub fn list(&self) -> ListRequestBuilder {
-        ListRequestBuilder {
-            client: &self.client,
-            base_url: self.base_url.clone(),
-            token: self.token.clone(),
-
-            size: None,
-        }
+    pub fn from_endpoint(endpoint: endpoint::Endpoint) -> Self {
+        WebhookClient { endpoint }
     }
 
-    pub fn get(&self, id: &str) -> error::Result<response::Response<WebhookResponse>> {
-        let url = self.base_url.join(id)?;
-        debug!("Sending webhook get request to {}", url.to_string());
-        let resp = self
-            .client
-            .get(url)
-            .bearer_auth(&self.token)
-            .send()?
-            .json::<response::Response<WebhookResponse>>()?;
-        trace!("Webhook get request responded with {:?}", resp);
-        Ok(resp)
+    pub fn ping(&self, id: &WebhookId) -> error::Result<response::Response<WebhookPing>> {
+        let url = self.endpoint.url(&format!("{}/ping", id))?;
+        debug!("Pinging webhook {}", id);
+        self.endpoint
+            .fetch(self.endpoint.request(reqwest::Method::POST, url).body(""))
     }
 
-    pub fn ping(&self, id: &str) -> error::Result<response::Response<WebhookPing>> {
-        let url = self.base_url.join(&(id.to_owned() + "/"))?.join("ping")?;
-        debug!("Sending webhook ping rquest to {}", url.to_string());
-        let resp = self
-            .client
-            .post(url)
-            .body("")
-            .bearer_auth(&self.token)
-            .send()?
-            .json::<response::Response<WebhookPing>>()?;
-        trace!("Webhook ping rquest responded with {:?}", resp);
-        Ok(resp)
+    pub fn logs(&self, id: &WebhookId) -> LogListRequestBuilder {
+        endpoint::ListRequestBuilder::new(&self.endpoint, &format!("{}/logs", id))
     }
 
-    pub fn logs<'a>(&'a self, id: &'a str) -> LogListRequestBuilder<'a> {
-        LogListRequestBuilder {
-            client: &self.client,
-            base_url: self.base_url.clone(),
-            token: self.token.clone(),
-            id,
-
-            size: None,
-        }
+    pub fn delete(&self, id: &WebhookId) -> error::Result<()> {
+        let url = self.endpoint.url(id.as_str())?;
+        debug!("Deleting webhook {}", id);
+        self.endpoint
+            .execute(self.endpoint.request(reqwest::Method::DELETE, url))
     }
 
-    pub fn delete(&self, id: &str) -> error::Result<()> {
-        let url = self.base_url.join(id)?;
-        debug!("Sending delete webhook request to {}", url.to_string());
-        let resp = self.client.delete(url).bearer_auth(&self.token).send()?;
-        trace!("Delete webhook request responded with {:?}", resp);
-        Ok(())
+    pub fn register(
+        &self,
+        webhook: &Webhook,
+    ) -> error::Result<response::Response<WebhookResponse>> {
+        debug!("Registering webhook {:?}", webhook);
+        let url = self.endpoint.base_url().clone();
+        self.endpoint.fetch(
+            self.endpoint
+                .request(reqwest::Method::POST, url)
+                .json(webhook),
+        )
     }
-
-    pub fn register(&self, webhook: &Webhook) -> error::Result<response::Response<WebhookResponse>> {
-        debug!(
-            "Sending create webhook request to {}",
-            self.base_url.to_string()
-        );
-        let webhook_ser = serde_json::to_string(webhook)?;
-        let resp = self
-            .client
-            .post(self.base_url.clone())
-            .body(webhook_ser)
-            .bearer_auth(&self.token)
-            .send()?
-            .json::<response::Response<WebhookResponse>>()?;
-        trace!("Create webhook request responded with {:?}", resp);
-        Ok(resp)
-    }
-}
-
-pub struct LogListRequestBuilder<'a> {
-    client: &'a reqwest::blocking::Client,
-    base_url: Url,
-    token: String,
-    id: &'a str,
-
-    size: Option<u32>,
 }
 
-pub struct ListRequestBuilder<'a> {
-    client: &'a reqwest::blocking::Client,
-    base_url: Url,
-    token: String,
+impl ResourceClient<WebhookResponse> for WebhookClient {
+    type Id = WebhookId;
 
-    size: Option<u32>,
-}
-
-enum ListParams {
-    PageSize(u32),
-}
-
-impl serde::Serialize for ListParams {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        match self {
-            ListParams::PageSize(size) => {
-                let mut tu = serializer.serialize_tuple(2)?;
-                tu.serialize_element("page[size]")?;
-                tu.serialize_element(size)?;
-                tu.end()
-            }
-        }
+    fn endpoint(&self) -> &endpoint::Endpoint {
+        &self.endpoint
     }
 }
 
-impl<'a> ListRequestBuilder<'a> {
-    setter!(size, u32);
+pub type ListRequestBuilder = endpoint::ListRequestBuilder<WebhookResponse>;
 
-    pub fn exec(&self) -> error::Result<response::Response<Vec<WebhookResponse>>> {
-        let url = self.base_url.clone();
-        let mut query = vec![];
-        if let Some(size) = self.size {
-            query.push(ListParams::PageSize(size));
-        }
-        debug!("Sending list webhook request to {}", url);
-        let resp = self
-            .client
-            .get(url)
-            .bearer_auth(&self.token)
-            .query(&query)
-            .send()?
-            .json::<response::Response<Vec<WebhookResponse>>>()?;
-        trace!("Webhook list webhook request responded with: {:?}", resp);
-        Ok(resp)
-    }
-}
+pub type LogListRequestBuilder = endpoint::ListRequestBuilder<WebhookLogRecord>;
 
-impl<'a> LogListRequestBuilder<'a> {
-    setter!(size, u32);
-
-    pub fn exec(&self) -> error::Result<response::Response<Vec<WebhookLogRecord>>> {
-        let url = self
-            .base_url
-            .join(&(self.id.to_owned() + "/"))?
-            .join("logs")?;
-        let mut query = vec![];
-        if let Some(size) = self.size {
-            query.push(ListParams::PageSize(size));
-        }
-        debug!("Sending webhook log request to {}", url.to_string());
-        let resp = self
-            .client
-            .get(url)
-            .bearer_auth(&self.token)
-            .query(&query)
-            .send()?
-            .json::<response::Response<Vec<WebhookLogRecord>>>()?;
-        trace!("Webhook log request responded with: {:?}", resp);
-        Ok(resp)
-    }
-}
-
-pub type WebhookResponse = resource::Resource<Attributes, Relationships>;
+pub type WebhookResponse = resource::Resource<Attributes, Relationships, WebhookId>;
 
 pub type Webhook = DataContainer<SettableAttributes>;
 
-// Only implement serialize for webhook because not all DataContainers need to
-// be serializable.
-impl Serialize for Webhook {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let mut s = serializer.serialize_struct("Webhook", 1)?;
-        s.serialize_field("data", &self.data)?;
-        s.end()
-    }
-}
-
 impl Webhook {
     pub fn new(url: String, description: Option<String>) -> Self {
         Self {
@@ -204,44 +80,40 @@ impl Webhook {
     }
 }
 
-#[derive(Deserialize, Debug, Serialize)]
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct SettableAttributes {
     pub url: String,
     pub description: Option<String>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Attributes {
     pub url: String,
     pub description: Option<String>,
+    /// Only returned when the webhook is registered.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub secret_key: Option<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Relationships {
-    pub logs: LogRelationship,
+    pub logs: resource::LinksOnly,
 }
 
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-pub struct LogRelationship {
-    pub logs: Option<RelatedLinks>,
-}
-
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-pub struct RelatedLinks {
-    pub related: Option<String>,
+impl resource::Related for Relationships {
+    fn relationships(&self) -> Vec<resource::RelationshipRef<'_>> {
+        vec![self.logs.to_ref("logs")]
+    }
 }
 
-type WebhookLogRecord =
+pub type WebhookLogRecord =
     resource::Resource<WebhookLogRecordAttributes, WebhookLogRecordRelationships>;
 
-#[derive(Deserialize, Debug, Display)]
+#[derive(Deserialize, Serialize, Debug, Display, PartialEq, Clone)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum WebhookDeliveryStatus {
     Delivered,
@@ -249,7 +121,7 @@ pub enum WebhookDeliveryStatus {
     BadResponseCode,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct WebhookLogRecordAttributes {
     pub request: WebhookLogRequest,
@@ -258,26 +130,32 @@ pub struct WebhookLogRecordAttributes {
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct WebhookLogRequest {
     pub body: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct WebhookLogResponse {
     pub status_code: u32,
     pub body: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct WebhookLogRecordRelationships {}
 
-type WebhookPing = resource::Resource<WebhookPingAttributes, WebhookPingRelationships>;
+impl resource::Related for WebhookLogRecordRelationships {
+    fn relationships(&self) -> Vec<resource::RelationshipRef<'_>> {
+        vec![]
+    }
+}
+
+pub type WebhookPing = resource::Resource<WebhookPingAttributes, WebhookPingRelationships>;
 
-#[derive(Serialize, Deserialize, Debug, Display)]
+#[derive(Serialize, Deserialize, Debug, Display, PartialEq, Clone)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum EventType {
     TransactionCreated,
@@ -286,44 +164,50 @@ pub enum EventType {
     Ping,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct WebhookPingAttributes {
     pub event_type: EventType,
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct WebhookPingRelationships {
-    pub webhook: Relationship,
-    pub transaction: Option<Relationship>,
+    pub webhook: resource::ToOne<WebhookId>,
+    pub transaction: Option<resource::ToOne<transaction::TransactionId>>,
+}
+
+impl resource::Related for WebhookPingRelationships {
+    fn relationships(&self) -> Vec<resource::RelationshipRef<'_>> {
+        let mut relationships = vec![self.webhook.to_ref("webhook")];
+        relationships.extend(self.transaction.iter().map(|t| t.to_ref("transaction")));
+        relationships
+    }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct DataContainer<T> {
     #[serde(bound(deserialize = "T: Deserialize<'de>"))]
     data: T,
-    links: Option<RelatedLinks>,
+    // Left out when registering a webhook since UpBank doesn't accept it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    links: Option<resource::RelationshipLinks>,
 }
 
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "camelCase")]
-pub struct TypeAndId {
-    #[serde(rename = "type")]
-    pub resource_type: resource::ResourceType,
-    pub id: String,
-}
-
-pub type Relationship = DataContainer<TypeAndId>;
-
 #[cfg(test)]
 mod test {
-    use super::Webhook;
+    use super::{Webhook, WebhookResponse};
     use crate::response::SuccessfulResponse;
-    use crate::test_deserialization;
+    use crate::{test_deserialization, test_round_trip};
 
     test_deserialization!(test_de, "webhook.json", Webhook);
     test_deserialization!(test_multi_de, "webhook_list.json", Vec<Webhook>);
+    test_round_trip!(test_round_trip, "webhook.json", WebhookResponse);
+    test_round_trip!(
+        test_multi_round_trip,
+        "webhook_list.json",
+        Vec<WebhookResponse>
+    );
 }
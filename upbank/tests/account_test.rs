@@ -1,6 +1,7 @@
 mod shared;
 
 use shared::{get_client, init_logger};
+use upbank::ResourceClient;
 
 #[test]
 fn test_list_accounts() {
@@ -25,7 +26,7 @@ fn test_get_account() {
         std::env::var("UPBANK_ACCOUNT_ID").expect("No env var UPBANK_ACCOUNT_ID found");
     let account = get_client()
         .account
-        .get(account_id)
+        .get(&account_id.into())
         .expect("Error response from account request");
     assert!(account.is_ok(), "Expected account to be ok: {:?}", account);
 }
@@ -35,6 +36,10 @@ fn test_list_account_transactions() {
     init_logger();
     let account_id =
         std::env::var("UPBANK_ACCOUNT_ID").expect("No env var UPBANK_ACCOUNT_ID found");
-    let transactions = get_client().account.transactions(account_id).exec().unwrap();
+    let transactions = get_client()
+        .account
+        .transactions(&account_id.into())
+        .exec()
+        .unwrap();
     assert!(transactions.is_ok(), "Expected ok: {:?}", transactions);
 }
@@ -0,0 +1,23 @@
+mod shared;
+
+use shared::{get_client, init_logger};
+use upbank::ResourceClient;
+
+#[test]
+fn test_expand_transaction() {
+    init_logger();
+    let client = get_client();
+    let id = std::env::var("UPBANK_TRANSACTION_ID").unwrap();
+    let transaction = match client.transaction.get(&id.into()).unwrap() {
+        upbank::response::Response::Ok(t) => t.data,
+        upbank::response::Response::Err(e) => panic!("Expected transaction to be ok: {}", e),
+    };
+    let account_id = transaction
+        .relationships
+        .account
+        .data
+        .as_ref()
+        .map(|a| a.id.clone());
+    let expanded = client.expand_transaction(transaction).unwrap();
+    assert_eq!(expanded.account.map(|a| a.id.clone()), account_id);
+}
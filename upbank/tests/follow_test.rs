@@ -0,0 +1,30 @@
+mod shared;
+
+use shared::{get_client, init_logger};
+use upbank::response::Response;
+use upbank::ResourceClient;
+
+#[test]
+fn test_follow_rejects_foreign_link() {
+    init_logger();
+    let client = upbank::Client::default_client("not-a-real-token".to_string());
+    let resp = client.follow::<upbank::account::Account>("https://example.com/api/v1/accounts/1");
+    assert!(resp.is_err(), "Expected foreign link to be rejected");
+}
+
+#[test]
+fn test_refresh_account() {
+    init_logger();
+    let client = get_client();
+    let account_id =
+        std::env::var("UPBANK_ACCOUNT_ID").expect("No env var UPBANK_ACCOUNT_ID found");
+    let account = match client.account.get(&account_id.into()).unwrap() {
+        Response::Ok(a) => a.data,
+        Response::Err(e) => panic!("Expected account to be ok: {}", e),
+    };
+    let refreshed = account.refresh(&client).unwrap();
+    match refreshed {
+        Response::Ok(a) => assert_eq!(a.data.id, account.id),
+        Response::Err(e) => panic!("Expected refreshed account to be ok: {}", e),
+    }
+}
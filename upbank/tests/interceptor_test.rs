@@ -0,0 +1,73 @@
+// Tests for request interceptors. These don't need UpBank to be reachable.
+mod shared;
+
+use shared::init_logger;
+use std::sync::{Arc, Mutex};
+use upbank::interceptor::{
+    Interceptor, MetricsInterceptor, RequestInfo, ResponseInfo, TracingInterceptor,
+};
+use upbank::Client;
+
+#[derive(Default)]
+struct Recorder {
+    seen: Mutex<Vec<(String, bool)>>,
+}
+
+impl Interceptor for Recorder {
+    fn on_request(&self, request: &RequestInfo) {
+        self.seen
+            .lock()
+            .unwrap()
+            .push((format!("{} {}", request.method, request.url), false));
+    }
+
+    fn on_response(&self, _request: &RequestInfo, response: &ResponseInfo) {
+        self.seen
+            .lock()
+            .unwrap()
+            .push((format!("{:?}", response.status), response.is_failure()));
+    }
+}
+
+fn unreachable_client() -> Client {
+    // Nothing listens on port 9 (discard) locally.
+    let url = url::Url::parse("http://127.0.0.1:9/api/v1/").unwrap();
+    Client::new(url, "token".to_string())
+}
+
+#[test]
+fn test_interceptors_see_every_request() {
+    init_logger();
+    let client = unreachable_client();
+    let recorder = Arc::new(Recorder::default());
+    let metrics = Arc::new(MetricsInterceptor::default());
+    client.add_interceptor(recorder.clone());
+    client.add_interceptor(metrics.clone());
+    client.add_interceptor(Arc::new(TracingInterceptor::default()));
+
+    let _ = client.health();
+    let _ = client.webhook.delete(&"abc".into());
+
+    assert_eq!(
+        *recorder.seen.lock().unwrap(),
+        vec![
+            ("GET http://127.0.0.1:9/api/v1/util/ping".to_string(), false),
+            ("None".to_string(), true),
+            (
+                "DELETE http://127.0.0.1:9/api/v1/webhooks/abc".to_string(),
+                false
+            ),
+            ("None".to_string(), true),
+        ]
+    );
+
+    let snapshot = metrics.snapshot();
+    let ping = &snapshot["GET /api/v1/util/ping"];
+    assert_eq!(ping.requests, 1);
+    assert_eq!(ping.failures, 1);
+    assert_eq!(ping.latency.count, 1);
+    assert_eq!(snapshot["DELETE /api/v1/webhooks/{id}"].requests, 1);
+
+    metrics.reset();
+    assert!(metrics.snapshot().is_empty());
+}
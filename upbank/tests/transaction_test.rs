@@ -1,6 +1,7 @@
 mod shared;
 
 use shared::{get_client, init_logger, start_of_month};
+use upbank::ResourceClient;
 
 #[test]
 fn test_transactions_list() {
@@ -13,7 +14,7 @@ fn test_transactions_list() {
 fn test_transaction_get() {
     init_logger();
     let id = std::env::var("UPBANK_TRANSACTION_ID").unwrap();
-    let transaction = get_client().transaction.get(id).unwrap();
+    let transaction = get_client().transaction.get(&id.into()).unwrap();
     assert!(transaction.is_ok());
 }
 
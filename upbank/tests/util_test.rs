@@ -2,7 +2,7 @@
 mod shared;
 
 use shared::{get_bad_client, get_client, init_logger};
-use upbank::util::PingResponse;
+use upbank::util::{HealthStatus, PingResponse};
 
 #[test]
 fn test_ping_ok() {
@@ -27,3 +27,32 @@ fn test_ping_401() {
     assert_eq!(error.title, "Not Authorized");
     assert_eq!(error.detail, "The request was not authenticated because no valid credential was found in the Authorization header, or the Authorization header was not present.");
 }
+
+#[test]
+fn test_health_ok() {
+    init_logger();
+    let health = get_client().health();
+    assert_eq!(health.status, HealthStatus::Healthy, "{:?}", health);
+    assert!(health.latency_ms.is_some());
+    assert!(health.clock_skew_secs.is_some());
+}
+
+#[test]
+fn test_health_invalid_token() {
+    init_logger();
+    let health = get_bad_client().health();
+    assert_eq!(health.status, HealthStatus::InvalidToken, "{:?}", health);
+    assert!(health.error.is_some());
+}
+
+#[test]
+fn test_health_unreachable() {
+    init_logger();
+    // Nothing listens on port 9 (discard) locally.
+    let url = url::Url::parse("http://127.0.0.1:9/api/v1/").unwrap();
+    let health = upbank::Client::new(url, "token".to_string()).health();
+    assert_eq!(health.status, HealthStatus::Unreachable, "{:?}", health);
+    assert_eq!(health.base_url, "http://127.0.0.1:9/api/v1/");
+    assert!(health.latency_ms.is_none());
+    assert!(health.error.is_some());
+}
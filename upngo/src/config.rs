@@ -0,0 +1,247 @@
+//! The config file, holding named profiles so switching between tokens (e.g.
+//! personal and joint) is just a matter of passing `--profile`.
+//!
+//! ```toml
+//! default_profile = "personal"
+//!
+//! [profiles.personal]
+//! token_command = "pass show up/personal"
+//! output = "json"
+//! timezone = "Australia/Perth"
+//!
+//! [profiles.personal.aliases]
+//! spending = "139ed96d-7f2a-4f2e-9c4a-1f1b2b0e8c7d"
+//!
+//! [profiles.joint]
+//! base_url = "https://api.up.com.au/api/v1/"
+//! token_file = "~/.config/upngo/joint-token"
+//! ```
+
+use crate::output::OutputFormat;
+use anyhow::{anyhow, Context, Result};
+use log::*;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Profile to use when `--profile` isn't given.
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub base_url: Option<String>,
+    /// File holding the token. A leading `~/` is expanded to the home
+    /// directory.
+    pub token_file: Option<String>,
+    /// Shell command that prints the token, e.g. `pass show up`.
+    pub token_command: Option<String>,
+    /// Output format used when `--output` isn't given.
+    pub output: Option<OutputFormat>,
+    /// IANA timezone to show times in and that dates like "today" are in,
+    /// when `--tz` isn't given. Defaults to Australia/Melbourne.
+    pub timezone: Option<chrono_tz::Tz>,
+    /// Short names for accounts, mapped to their IDs.
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/upngo/config.toml`, falling back to
+    /// `~/.config/upngo/config.toml`.
+    pub fn default_path() -> Option<PathBuf> {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| home_dir().map(|home| home.join(".config")))
+            .map(|dir| dir.join("upngo").join("config.toml"))
+    }
+
+    /// Load the config at the default path, if there's a file there.
+    pub fn load_default() -> Result<Config> {
+        match Self::default_path() {
+            Some(path) if path.exists() => Self::load(&path),
+            _ => {
+                debug!("No config file found");
+                Ok(Config::default())
+            }
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Config> {
+        debug!("Loading config from {}", path.display());
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        Self::parse(&contents)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))
+    }
+
+    pub fn parse(contents: &str) -> Result<Config> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    /// The profile called `name`, or the default profile if it's `None`. The
+    /// default is `default_profile` if set, otherwise the profile called
+    /// "default" if there is one, otherwise an empty profile.
+    pub fn profile(&self, name: Option<&str>) -> Result<Profile> {
+        let name = match name.or(self.default_profile.as_deref()) {
+            Some(name) => name,
+            None => return Ok(self.profiles.get("default").cloned().unwrap_or_default()),
+        };
+        self.profiles
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow!("No profile named '{}' in the config file", name))
+    }
+}
+
+impl Profile {
+    /// Read the profile's token from its file or command, if it has either.
+    pub fn token(&self) -> Result<Option<upbank::Token>> {
+        match (&self.token_file, &self.token_command) {
+            (Some(_), Some(_)) => Err(anyhow!(
+                "A profile can't have both token_file and token_command"
+            )),
+            (Some(path), None) => read_token_file(&expand_home(path)).map(Some),
+            (None, Some(command)) => run_token_command(command).map(Some),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// The account ID `account` is an alias of, if it's an alias.
+    pub fn resolve_alias(&self, account: &str) -> Option<&str> {
+        self.aliases.get(account).map(String::as_str)
+    }
+}
+
+/// Read the token from `path`, or stdin if it's "-", ignoring surrounding
+/// whitespace.
+pub fn read_token_file(path: &str) -> Result<upbank::Token> {
+    use std::io::Read;
+    // Wipe the untrimmed copy too.
+    let mut contents = zeroize::Zeroizing::new(String::new());
+    if path == "-" {
+        debug!("Reading UpBank token from stdin");
+        std::io::stdin()
+            .read_to_string(&mut contents)
+            .context("Failed to read token from stdin")?;
+    } else {
+        debug!("Reading UpBank token from {}", path);
+        std::fs::File::open(path)
+            .and_then(|mut f| f.read_to_string(&mut contents))
+            .with_context(|| format!("Failed to read token from {}", path))?;
+    }
+    let token = contents.trim();
+    if token.is_empty() {
+        return Err(anyhow!("Token file {} is empty", path));
+    }
+    Ok(upbank::Token::new(token))
+}
+
+/// Run `command` with the shell and use what it prints as the token. Its
+/// stdin and stderr are left attached so it can prompt for a passphrase.
+fn run_token_command(command: &str) -> Result<upbank::Token> {
+    debug!("Running token command '{}'", command);
+    let output = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(std::process::Stdio::inherit())
+        .stderr(std::process::Stdio::inherit())
+        .output()
+        .with_context(|| format!("Failed to run token command '{}'", command))?;
+    let stdout = zeroize::Zeroizing::new(output.stdout);
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Token command '{}' failed with {}",
+            command,
+            output.status
+        ));
+    }
+    let token = std::str::from_utf8(&stdout)
+        .with_context(|| format!("Token command '{}' printed invalid UTF-8", command))?
+        .trim();
+    if token.is_empty() {
+        return Err(anyhow!("Token command '{}' printed nothing", command));
+    }
+    Ok(upbank::Token::new(token))
+}
+
+pub fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest).to_string_lossy().into_owned(),
+        _ => path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Config, Profile};
+    use crate::output::OutputFormat;
+
+    const CONFIG: &str = r#"
+        default_profile = "personal"
+
+        [profiles.personal]
+        token_command = "echo '  up:yeah:personal  '"
+        output = "json"
+        timezone = "Australia/Perth"
+
+        [profiles.personal.aliases]
+        spending = "139ed96d"
+
+        [profiles.joint]
+        base_url = "http://localhost:8080/api/v1/"
+        token_file = "/nonexistent/upngo-token"
+    "#;
+
+    #[test]
+    fn test_profile() {
+        let config = Config::parse(CONFIG).unwrap();
+        let personal = config.profile(None).unwrap();
+        assert_eq!(personal.output, Some(OutputFormat::Json));
+        assert_eq!(personal.timezone, Some(chrono_tz::Australia::Perth));
+        assert_eq!(personal.resolve_alias("spending"), Some("139ed96d"));
+        assert_eq!(personal.resolve_alias("saver"), None);
+        assert_eq!(
+            personal.token().unwrap().unwrap().expose_secret(),
+            "up:yeah:personal"
+        );
+
+        let joint = config.profile(Some("joint")).unwrap();
+        assert_eq!(
+            joint.base_url.as_deref(),
+            Some("http://localhost:8080/api/v1/")
+        );
+        assert!(joint.token().is_err());
+
+        assert!(config.profile(Some("business")).is_err());
+    }
+
+    #[test]
+    fn test_default_profile() {
+        assert_eq!(Config::default().profile(None).unwrap(), Profile::default());
+
+        let config = Config::parse("[profiles.default]\noutput = \"table\"").unwrap();
+        assert_eq!(
+            config.profile(None).unwrap().output,
+            Some(OutputFormat::Table)
+        );
+    }
+
+    #[test]
+    fn test_unknown_fields_rejected() {
+        assert!(Config::parse("[profiles.personal]\ntoken = \"up:yeah:oops\"").is_err());
+    }
+}
@@ -0,0 +1,373 @@
+//! Dates given on the command line, e.g. for `--since`, `--until` and
+//! `--period`, and how dates are shown. Days, months and so on start at
+//! midnight in the local timezone, so "yesterday" is the day people think of
+//! rather than the one in UTC.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// Timezone used when the profile doesn't set one. UpBank is Melbourne
+/// based, so this is the zone its app shows days in.
+pub const DEFAULT_TIMEZONE: Tz = chrono_tz::Australia::Melbourne;
+
+/// Furthest back "7d" or "2w" can go, about a century.
+const MAX_DAYS_AGO: u32 = 36_500;
+
+/// How times are shown in tables and templates. The machine readable formats
+/// keep UpBank's times as they are.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DateStyle {
+    pub tz: Tz,
+    /// Show just the date, without the time.
+    pub date_only: bool,
+}
+
+impl Default for DateStyle {
+    fn default() -> Self {
+        DateStyle {
+            tz: DEFAULT_TIMEZONE,
+            date_only: false,
+        }
+    }
+}
+
+impl DateStyle {
+    pub fn format(&self, at: DateTime<Utc>) -> String {
+        let spec = if self.date_only {
+            "%Y-%m-%d"
+        } else {
+            "%Y-%m-%d %H:%M:%S %Z"
+        };
+        self.format_with(at, spec)
+    }
+
+    /// Format `at` in `tz` with a strftime spec.
+    pub fn format_with(&self, at: DateTime<Utc>, spec: &str) -> String {
+        at.with_timezone(&self.tz).format(spec).to_string()
+    }
+
+    /// The day `at` is on in `tz`.
+    pub fn day(&self, at: DateTime<Utc>) -> NaiveDate {
+        at.with_timezone(&self.tz).date_naive()
+    }
+
+    /// Split `items` into runs on the same day in `tz`, keeping their order.
+    /// Items on the same day are expected to be next to each other, as they
+    /// are in UpBank's lists.
+    pub fn group_by_day<'a, T>(
+        &self,
+        items: &'a [T],
+        at: impl Fn(&T) -> DateTime<Utc>,
+    ) -> Vec<(NaiveDate, &'a [T])> {
+        let mut groups = vec![];
+        let mut start = 0;
+        for i in 1..=items.len() {
+            let day = self.day(at(&items[start]));
+            if i == items.len() || self.day(at(&items[i])) != day {
+                groups.push((day, &items[start..i]));
+                start = i;
+            }
+        }
+        groups
+    }
+}
+
+/// A date or period, relative to now or absolute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateSpec {
+    Today,
+    Yesterday,
+    /// From this many days ago until now.
+    DaysAgo(u32),
+    ThisMonth,
+    LastMonth,
+    Day(NaiveDate),
+    /// The month starting on the given date.
+    Month(NaiveDate),
+    /// The quarter starting on the given date.
+    Quarter(NaiveDate),
+    /// An exact point in time.
+    At(DateTime<Utc>),
+}
+
+impl std::str::FromStr for DateSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_lowercase();
+        let spec = match lower.as_str() {
+            "today" => Some(DateSpec::Today),
+            "yesterday" => Some(DateSpec::Yesterday),
+            "this-month" => Some(DateSpec::ThisMonth),
+            "last-month" => Some(DateSpec::LastMonth),
+            _ => parse_days_ago(&lower)
+                .or_else(|| parse_quarter(&lower))
+                .or_else(|| {
+                    NaiveDate::parse_from_str(&lower, "%Y-%m-%d")
+                        .ok()
+                        .map(DateSpec::Day)
+                })
+                .or_else(|| {
+                    NaiveDate::parse_from_str(&format!("{}-01", lower), "%Y-%m-%d")
+                        .ok()
+                        .map(DateSpec::Month)
+                })
+                .or_else(|| {
+                    DateTime::parse_from_rfc3339(s)
+                        .ok()
+                        .map(|at| DateSpec::At(at.with_timezone(&Utc)))
+                }),
+        };
+        match spec {
+            Some(DateSpec::DaysAgo(days)) if days > MAX_DAYS_AGO => Err(format!(
+                "Invalid date '{}', can't go back more than {} days",
+                s, MAX_DAYS_AGO
+            )),
+            Some(spec) => Ok(spec),
+            None => Err(format!(
+                "Invalid date '{}', expected today, yesterday, 7d, 2w, this-month, last-month, 2024-03, 2024-Q1, 2024-03-15 or an RFC 3339 time",
+                s
+            )),
+        }
+    }
+}
+
+/// "7d" or "2w". Counts too large for a `u32` of days are `u32::MAX`, so
+/// they're rejected as too far back rather than as not a date.
+fn parse_days_ago(s: &str) -> Option<DateSpec> {
+    let (count, days_per) = match s.strip_suffix('d') {
+        Some(count) => (count, 1),
+        None => (s.strip_suffix('w')?, 7),
+    };
+    if count.is_empty() || !count.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let days = count
+        .parse::<u32>()
+        .ok()
+        .and_then(|count| count.checked_mul(days_per))
+        .unwrap_or(u32::MAX);
+    Some(DateSpec::DaysAgo(days))
+}
+
+/// "2024-q1".
+fn parse_quarter(s: &str) -> Option<DateSpec> {
+    let (year, quarter) = s.split_once("-q")?;
+    let quarter: u32 = match quarter.parse() {
+        Ok(quarter @ 1..=4) => quarter,
+        _ => return None,
+    };
+    let start = NaiveDate::from_ymd_opt(year.parse().ok()?, (quarter - 1) * 3 + 1, 1)?;
+    Some(DateSpec::Quarter(start))
+}
+
+impl DateSpec {
+    /// The start (inclusive) and end (exclusive) of the period, with days
+    /// starting at midnight in `tz`. A point in time is a period that
+    /// starts and ends at that time. Periods too far in the past or future
+    /// to be represented are an error.
+    pub fn range(
+        &self,
+        tz: Tz,
+        now: DateTime<Utc>,
+    ) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+        let today = now.with_timezone(&tz).date_naive();
+        let month = |date: NaiveDate, months: i32| -> Option<NaiveDate> {
+            let months = date.year() * 12 + date.month0() as i32 + months;
+            NaiveDate::from_ymd_opt(months.div_euclid(12), months.rem_euclid(12) as u32 + 1, 1)
+        };
+        let days = |start: Option<NaiveDate>, end: Option<NaiveDate>| {
+            Some((midnight(tz, start?)?, midnight(tz, end?)?))
+        };
+        let range = match *self {
+            DateSpec::Today => days(Some(today), today.succ_opt()),
+            DateSpec::Yesterday => days(today.pred_opt(), Some(today)),
+            DateSpec::DaysAgo(count) => {
+                let start = Duration::try_days(count.into())
+                    .and_then(|ago| now.checked_sub_signed(ago))
+                    .ok_or_else(|| format!("Can't go back {} days", count))?;
+                Some((start, now))
+            }
+            DateSpec::ThisMonth => days(month(today, 0), month(today, 1)),
+            DateSpec::LastMonth => days(month(today, -1), month(today, 0)),
+            DateSpec::Day(day) => days(Some(day), day.succ_opt()),
+            DateSpec::Month(start) => days(Some(start), month(start, 1)),
+            DateSpec::Quarter(start) => days(Some(start), month(start, 3)),
+            DateSpec::At(at) => Some((at, at)),
+        };
+        range.ok_or_else(|| "Dates that far from now aren't supported".to_string())
+    }
+
+    /// Where `--since` with this date starts.
+    pub fn since(&self, tz: Tz, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+        self.range(tz, now).map(|(since, _)| since)
+    }
+
+    /// Where `--until` with this date ends: the end of a day or month, but
+    /// "7d" means until 7 days ago rather than until now.
+    pub fn until(&self, tz: Tz, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+        match self {
+            DateSpec::DaysAgo(_) => self.since(tz, now),
+            _ => self.range(tz, now).map(|(_, until)| until),
+        }
+    }
+
+    /// Whether this is a point in time rather than a period.
+    pub fn is_instant(&self) -> bool {
+        matches!(self, DateSpec::At(_))
+    }
+}
+
+/// The start of `date` in `tz`. If midnight is skipped by a daylight saving
+/// change, it's the first time that does exist. `None` if the day can't be
+/// represented in UTC, at the very ends of the calendar.
+fn midnight(tz: Tz, date: NaiveDate) -> Option<DateTime<Utc>> {
+    let mut time = date.and_hms_opt(0, 0, 0)?;
+    // No daylight saving change skips a whole day.
+    while time.date() == date {
+        if let Some(start) = tz.from_local_datetime(&time).earliest() {
+            return Some(start.with_timezone(&Utc));
+        }
+        time = time.checked_add_signed(Duration::minutes(30))?;
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DateSpec, DateStyle, DEFAULT_TIMEZONE};
+    use chrono::{DateTime, NaiveDate, Utc};
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn range(spec: &str, now: &str) -> (String, String) {
+        let (start, end) = spec
+            .parse::<DateSpec>()
+            .unwrap()
+            .range(DEFAULT_TIMEZONE, utc(now))
+            .unwrap();
+        (start.to_rfc3339(), end.to_rfc3339())
+    }
+
+    #[test]
+    fn test_parse() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!("Today".parse(), Ok(DateSpec::Today));
+        assert_eq!("7d".parse(), Ok(DateSpec::DaysAgo(7)));
+        assert_eq!("2w".parse(), Ok(DateSpec::DaysAgo(14)));
+        assert_eq!("2024-03".parse(), Ok(DateSpec::Month(date(2024, 3, 1))));
+        assert_eq!("2024-Q2".parse(), Ok(DateSpec::Quarter(date(2024, 4, 1))));
+        assert_eq!("2024-03-15".parse(), Ok(DateSpec::Day(date(2024, 3, 15))));
+        assert_eq!(
+            "2020-09-01T00:00:00Z".parse(),
+            Ok(DateSpec::At(utc("2020-09-01T00:00:00Z")))
+        );
+        for invalid in &[
+            "",
+            "d",
+            "7x",
+            "2024-Q5",
+            "2024-13",
+            "2024-02-30",
+            "tomorrow",
+            "+7d",
+            "é",
+            "7é",
+            "éd",
+            "36501d",
+            "99999999999d",
+            "999999999w",
+        ] {
+            assert!(invalid.parse::<DateSpec>().is_err(), "{}", invalid);
+        }
+        assert_eq!("36500d".parse(), Ok(DateSpec::DaysAgo(36500)));
+    }
+
+    #[test]
+    fn test_range() {
+        // 11pm on the 1st of March in Melbourne (AEDT, +11:00).
+        let now = "2024-03-01T12:00:00Z";
+        assert_eq!(
+            range("today", now),
+            (
+                "2024-02-29T13:00:00+00:00".to_string(),
+                "2024-03-01T13:00:00+00:00".to_string()
+            )
+        );
+        assert_eq!(
+            range("last-month", now),
+            (
+                "2024-01-31T13:00:00+00:00".to_string(),
+                "2024-02-29T13:00:00+00:00".to_string()
+            )
+        );
+        let seven_days = "7d".parse::<DateSpec>().unwrap();
+        assert_eq!(
+            seven_days.until(DEFAULT_TIMEZONE, utc(now)),
+            Ok(utc("2024-02-23T12:00:00Z"))
+        );
+        assert_eq!(
+            range("7d", now),
+            (
+                "2024-02-23T12:00:00+00:00".to_string(),
+                "2024-03-01T12:00:00+00:00".to_string()
+            )
+        );
+        // Daylight saving ends on the 7th of April, so the quarter starts
+        // at +11:00 and ends at +10:00.
+        assert_eq!(
+            range("2024-Q2", now),
+            (
+                "2024-03-31T13:00:00+00:00".to_string(),
+                "2024-06-30T14:00:00+00:00".to_string()
+            )
+        );
+        assert_eq!(
+            range("last-month", "2024-01-15T00:00:00Z"),
+            (
+                "2023-11-30T13:00:00+00:00".to_string(),
+                "2023-12-31T13:00:00+00:00".to_string()
+            )
+        );
+        assert!(DateSpec::DaysAgo(u32::MAX)
+            .range(DEFAULT_TIMEZONE, utc(now))
+            .is_err());
+        // Valid dates whose period ends past the last date chrono supports.
+        for far in &["262142-Q4", "+262142-12", "+262142-12-31"] {
+            let spec = far.parse::<DateSpec>().unwrap();
+            assert!(spec.range(DEFAULT_TIMEZONE, utc(now)).is_err(), "{}", far);
+            assert!(spec.until(DEFAULT_TIMEZONE, utc(now)).is_err(), "{}", far);
+        }
+    }
+
+    #[test]
+    fn test_group_by_day() {
+        // 1am and 11:30pm in Melbourne are on different days, even though
+        // they're on the same day in UTC.
+        let times = vec![
+            utc("2024-03-02T10:00:00Z"),
+            utc("2024-03-02T01:00:00Z"),
+            utc("2024-03-01T14:00:00Z"),
+            utc("2024-03-01T12:30:00Z"),
+        ];
+        let style = DateStyle::default();
+        let groups: Vec<(String, usize)> = style
+            .group_by_day(&times, |t| *t)
+            .into_iter()
+            .map(|(day, group)| (day.to_string(), group.len()))
+            .collect();
+        assert_eq!(
+            groups,
+            vec![("2024-03-02".to_string(), 3), ("2024-03-01".to_string(), 1)]
+        );
+        assert!(style
+            .group_by_day(&[] as &[chrono::DateTime<chrono::Utc>], |t| *t)
+            .is_empty());
+        assert_eq!(
+            style.format(utc("2024-03-01T14:00:00Z")),
+            "2024-03-02 01:00:00 AEDT"
+        );
+    }
+}
@@ -0,0 +1,551 @@
+//! Picking which fields of a resource to show, either as table columns
+//! (`--columns description,amount`) or in a line per resource
+//! (`--format '{created_at:%Y-%m-%d} {amount} {description}'`).
+
+use crate::dates::DateStyle;
+use crate::output::{self, OutputFormat};
+use anyhow::{anyhow, Result};
+use prettytable::{Cell, Row, Table};
+use serde::Serialize;
+use upbank::{account::Account, category::Category, transaction::Transaction};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Text,
+    /// Can be formatted with a strftime spec.
+    Date,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Field {
+    pub name: &'static str,
+    /// Column header.
+    pub header: &'static str,
+    pub kind: Kind,
+}
+
+const fn text(name: &'static str, header: &'static str) -> Field {
+    Field {
+        name,
+        header,
+        kind: Kind::Text,
+    }
+}
+
+const fn date(name: &'static str, header: &'static str) -> Field {
+    Field {
+        name,
+        header,
+        kind: Kind::Date,
+    }
+}
+
+/// The value of a field of a resource.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Text(String),
+    Date(chrono::DateTime<chrono::Utc>),
+    /// The resource doesn't have a value for the field, e.g. an unsettled
+    /// transaction's settled_at.
+    Missing,
+}
+
+impl Value {
+    fn render(&self, spec: Option<&str>, missing: &str, dates: &DateStyle) -> String {
+        match (self, spec) {
+            (Value::Text(s), _) => s.clone(),
+            (Value::Date(d), Some(spec)) => dates.format_with(*d, spec),
+            (Value::Date(d), None) => dates.format(*d),
+            (Value::Missing, _) => missing.to_string(),
+        }
+    }
+}
+
+impl<T: ToString> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Missing, |v| Value::Text(v.to_string()))
+    }
+}
+
+/// Implemented by each resource whose fields can be picked.
+pub trait Fields {
+    const FIELDS: &'static [Field];
+    /// Columns shown when `--columns` isn't given.
+    const DEFAULT_COLUMNS: &'static [&'static str];
+
+    /// The value of the field called `name`, one of `FIELDS`.
+    fn value(&self, name: &str) -> Value;
+
+    fn field(name: &str) -> Result<&'static Field> {
+        Self::FIELDS.iter().find(|f| f.name == name).ok_or_else(|| {
+            let names: Vec<&str> = Self::FIELDS.iter().map(|f| f.name).collect();
+            anyhow!(
+                "Unknown field '{}', expected one of {}",
+                name,
+                names.join(", ")
+            )
+        })
+    }
+}
+
+impl Fields for Transaction {
+    const FIELDS: &'static [Field] = &[
+        text("id", "ID"),
+        text("status", "Status"),
+        text("description", "Description"),
+        text("raw_text", "Raw Text"),
+        text("message", "Message"),
+        text("amount", "Amount"),
+        text("held_amount", "Held Amount"),
+        text("foreign_amount", "Foreign Amount"),
+        text("fx_rate", "FX Rate"),
+        text("round_up", "Round Up"),
+        text("cashback", "Cashback"),
+        text("account", "Account"),
+        text("category", "Category"),
+        text("parent_category", "Parent Category"),
+        text("tags", "Tags"),
+        date("created_at", "Created"),
+        date("settled_at", "Settled"),
+    ];
+    const DEFAULT_COLUMNS: &'static [&'static str] = &[
+        "description",
+        "amount",
+        "status",
+        "created_at",
+        "settled_at",
+        "id",
+    ];
+
+    fn value(&self, name: &str) -> Value {
+        let attrs = &self.attributes;
+        match name {
+            "id" => Value::Text(self.id.to_string()),
+            "status" => Value::Text(attrs.status.to_string()),
+            "description" => Value::Text(attrs.description.clone()),
+            "raw_text" => attrs.raw_text.as_ref().into(),
+            "message" => attrs.message.as_ref().into(),
+            "amount" => Value::Text(self.amount().to_string()),
+            "held_amount" => self.held_amount().map(|m| m.to_string()).into(),
+            "foreign_amount" => self.foreign_amount().map(|m| m.to_string()).into(),
+            "fx_rate" => self.fx_rate().into(),
+            "round_up" => self.round_up().map(|m| m.to_string()).into(),
+            "cashback" => attrs.cashback.as_ref().map(|c| c.amount.to_string()).into(),
+            "account" => self.account_id().into(),
+            "category" => self.category_id().into(),
+            "parent_category" => self.parent_category_id().into(),
+            "tags" => {
+                let tags: Vec<&str> = self.tag_ids().iter().map(|t| t.as_str()).collect();
+                Value::Text(tags.join(", "))
+            }
+            "created_at" => Value::Date(attrs.created_at),
+            "settled_at" => attrs.settled_at.map_or(Value::Missing, Value::Date),
+            _ => Value::Missing,
+        }
+    }
+}
+
+impl Fields for Account {
+    const FIELDS: &'static [Field] = &[
+        text("id", "ID"),
+        text("display_name", "Name"),
+        text("account_type", "Type"),
+        text("ownership_type", "Ownership"),
+        text("balance", "Balance"),
+        date("created_at", "Created"),
+    ];
+    const DEFAULT_COLUMNS: &'static [&'static str] = &[
+        "display_name",
+        "balance",
+        "account_type",
+        "ownership_type",
+        "created_at",
+        "id",
+    ];
+
+    fn value(&self, name: &str) -> Value {
+        let attrs = &self.attributes;
+        match name {
+            "id" => Value::Text(self.id.to_string()),
+            "display_name" => Value::Text(attrs.display_name.clone()),
+            "account_type" => Value::Text(attrs.account_type.to_string()),
+            "ownership_type" => Value::Text(attrs.ownership_type.to_string()),
+            "balance" => Value::Text(attrs.balance.to_string()),
+            "created_at" => Value::Date(attrs.created_at),
+            _ => Value::Missing,
+        }
+    }
+}
+
+impl Fields for upbank::webhook::WebhookResponse {
+    const FIELDS: &'static [Field] = &[
+        text("id", "ID"),
+        text("url", "URL"),
+        text("description", "Description"),
+        date("created_at", "Created"),
+    ];
+    const DEFAULT_COLUMNS: &'static [&'static str] = &["description", "url", "created_at", "id"];
+
+    fn value(&self, name: &str) -> Value {
+        let attrs = &self.attributes;
+        match name {
+            "id" => Value::Text(self.id.to_string()),
+            "url" => Value::Text(attrs.url.clone()),
+            "description" => attrs.description.as_ref().into(),
+            "created_at" => Value::Date(attrs.created_at),
+            _ => Value::Missing,
+        }
+    }
+}
+
+impl Fields for Category {
+    const FIELDS: &'static [Field] = &[
+        text("id", "ID"),
+        text("name", "Name"),
+        text("parent", "Parent"),
+        text("children", "Children"),
+    ];
+    const DEFAULT_COLUMNS: &'static [&'static str] = &["name", "id"];
+
+    fn value(&self, name: &str) -> Value {
+        let relationships = &self.relationships;
+        match name {
+            "id" => Value::Text(self.id.to_string()),
+            "name" => Value::Text(self.attributes.name.clone()),
+            "parent" => relationships.parent.data.as_ref().map(|p| &p.id).into(),
+            "children" => {
+                let children: Vec<&str> = relationships
+                    .children
+                    .data
+                    .iter()
+                    .map(|c| c.id.as_str())
+                    .collect();
+                Value::Text(children.join(", "))
+            }
+            _ => Value::Missing,
+        }
+    }
+}
+
+/// The fields to show as columns, in order.
+#[derive(Debug)]
+pub struct Columns(Vec<&'static Field>);
+
+impl Columns {
+    /// Parse a comma separated list of field names, or use the resource's
+    /// default columns if there isn't one.
+    pub fn parse<T: Fields>(columns: Option<&str>) -> Result<Columns> {
+        let names: Vec<&str> = match columns {
+            Some(columns) => columns.split(',').map(str::trim).collect(),
+            None => T::DEFAULT_COLUMNS.to_vec(),
+        };
+        Ok(Columns(
+            names
+                .into_iter()
+                .map(T::field)
+                .collect::<Result<Vec<_>>>()?,
+        ))
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.0.iter().map(|f| f.name).collect()
+    }
+
+    pub fn header(&self) -> Row {
+        Row::new(self.0.iter().map(|f| Cell::new(f.header)).collect())
+    }
+
+    /// The resource's values for each column, with missing values shown as
+    /// `missing`.
+    pub fn values<T: Fields>(&self, resource: &T, missing: &str, dates: &DateStyle) -> Vec<String> {
+        self.0
+            .iter()
+            .map(|f| resource.value(f.name).render(None, missing, dates))
+            .collect()
+    }
+
+    pub fn table<T: Fields>(&self, resources: &[T], dates: &DateStyle) -> Table {
+        let mut table = Table::new();
+        table.add_row(self.header());
+        for resource in resources {
+            table.add_row(Row::new(
+                self.values(resource, "N/A", dates)
+                    .iter()
+                    .map(|v| Cell::new(v))
+                    .collect(),
+            ));
+        }
+        table
+    }
+}
+
+/// How to show a list of resources, from `--columns` and `--format`.
+#[derive(Debug)]
+pub struct Selection {
+    columns: Columns,
+    /// Whether the columns were picked rather than the defaults.
+    picked: bool,
+    template: Option<Template>,
+    dates: DateStyle,
+}
+
+impl Selection {
+    pub fn parse<T: Fields>(
+        columns: Option<&str>,
+        template: Option<&str>,
+        dates: DateStyle,
+    ) -> Result<Selection> {
+        Ok(Selection {
+            columns: Columns::parse::<T>(columns)?,
+            picked: columns.is_some(),
+            template: template.map(Template::parse::<T>).transpose()?,
+            dates,
+        })
+    }
+
+    pub fn columns(&self) -> &Columns {
+        &self.columns
+    }
+
+    pub fn dates(&self) -> &DateStyle {
+        &self.dates
+    }
+
+    /// Whether the resources are printed with a template rather than in an
+    /// output format.
+    pub fn has_template(&self) -> bool {
+        self.template.is_some()
+    }
+
+    /// Whether resources are printed independently of each other, so a list
+    /// can be printed a page at a time.
+    pub fn streams(&self, format: OutputFormat) -> bool {
+        self.template.is_some() || format == OutputFormat::Jsonl
+    }
+
+    /// Print a line per resource if there's a template, otherwise print in
+    /// `format`. Picked columns apply to tables and CSV, the other formats
+    /// always have the whole resource.
+    pub fn print_list<T: Fields + Serialize>(
+        &self,
+        format: OutputFormat,
+        resources: &[T],
+    ) -> Result<()> {
+        if let Some(template) = &self.template {
+            let stdout = std::io::stdout();
+            let mut out = stdout.lock();
+            for resource in resources {
+                use std::io::Write;
+                writeln!(out, "{}", template.render(resource, &self.dates))?;
+            }
+            return Ok(());
+        }
+        match format {
+            OutputFormat::Csv if self.picked => {
+                let rows: Vec<Vec<String>> = resources
+                    .iter()
+                    .map(|r| self.columns.values(r, "", &self.dates))
+                    .collect();
+                output::print_csv(&self.columns.names(), &rows)
+            }
+            format => output::print_list(format, resources, |resources| {
+                self.columns.table(resources, &self.dates)
+            }),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Literal(String),
+    Field {
+        name: &'static str,
+        spec: Option<String>,
+    },
+}
+
+/// A line of text with fields substituted in, like
+/// `{created_at:%Y-%m-%d} {amount} {description}`. Dates can be given a
+/// strftime spec after a colon. Use `{{` and `}}` for literal braces.
+#[derive(Debug, PartialEq)]
+pub struct Template(Vec<Segment>);
+
+impl Template {
+    pub fn parse<T: Fields>(template: &str) -> Result<Template> {
+        let mut segments = vec![];
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut placeholder = String::new();
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        placeholder.push(c);
+                    }
+                    if !closed {
+                        return Err(anyhow!("Unclosed '{{' in format '{}'", template));
+                    }
+                    let mut parts = placeholder.splitn(2, ':');
+                    let field = T::field(parts.next().unwrap_or("").trim())?;
+                    let spec = parts.next().map(str::to_string);
+                    if let Some(spec) = &spec {
+                        validate_spec(field, spec)?;
+                    }
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Field {
+                        name: field.name,
+                        spec,
+                    });
+                }
+                '}' => {
+                    return Err(anyhow!(
+                        "Unmatched '}}' in format '{}', use '}}}}' for a literal '}}'",
+                        template
+                    ))
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Template(segments))
+    }
+
+    pub fn render<T: Fields>(&self, resource: &T, dates: &DateStyle) -> String {
+        self.0
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(s) => s.clone(),
+                Segment::Field { name, spec } => {
+                    resource.value(name).render(spec.as_deref(), "", dates)
+                }
+            })
+            .collect()
+    }
+}
+
+fn validate_spec(field: &Field, spec: &str) -> Result<()> {
+    use chrono::format::{Item, StrftimeItems};
+    if field.kind != Kind::Date {
+        return Err(anyhow!(
+            "Field '{}' isn't a date so can't be given a format spec",
+            field.name
+        ));
+    }
+    if StrftimeItems::new(spec).any(|item| item == Item::Error) {
+        return Err(anyhow!("Invalid date format '{}'", spec));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Columns, Template};
+    use crate::dates::DateStyle;
+    use upbank::transaction::Transaction;
+
+    fn transaction() -> Transaction {
+        serde_json::from_value(serde_json::json!({
+            "type": "transactions",
+            "id": "52a0f1d6-8c10-4b1e-9d9e-2f1c8c3b5a71",
+            "attributes": {
+                "status": "SETTLED",
+                "rawText": "PIZZA PALACE MELBOURNE",
+                "description": "Pizza Palace",
+                "message": null,
+                "holdInfo": null,
+                "roundUp": null,
+                "cashback": null,
+                "amount": {
+                    "currencyCode": "AUD",
+                    "value": "-59.98",
+                    "valueInBaseUnits": -5998
+                },
+                "foreignAmount": null,
+                "settledAt": "2020-09-03T01:12:40Z",
+                "createdAt": "2020-09-02T20:35:27Z"
+            },
+            "relationships": {
+                "account": {
+                    "data": {
+                        "type": "accounts",
+                        "id": "139ed96d-9697-4c4a-b221-3d0f72d656cd"
+                    }
+                },
+                "category": { "data": null },
+                "parentCategory": { "data": null },
+                "tags": { "data": [] }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_columns() {
+        let columns =
+            Columns::parse::<Transaction>(Some("description, amount,settled_at")).unwrap();
+        assert_eq!(columns.names(), vec!["description", "amount", "settled_at"]);
+        let values = columns.values(&transaction(), "N/A", &DateStyle::default());
+        assert_eq!(values[0], transaction().attributes.description);
+        assert_eq!(values[1], transaction().attributes.amount.to_string());
+
+        assert!(Columns::parse::<Transaction>(Some("description,colour")).is_err());
+        assert_eq!(
+            Columns::parse::<Transaction>(None).unwrap().names(),
+            vec![
+                "description",
+                "amount",
+                "status",
+                "created_at",
+                "settled_at",
+                "id"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_template() {
+        let transaction = transaction();
+        let template =
+            Template::parse::<Transaction>("{created_at:%Y-%m-%d} {{{amount}}} {description}")
+                .unwrap();
+        let dates = DateStyle {
+            tz: chrono_tz::UTC,
+            date_only: false,
+        };
+        assert_eq!(
+            template.render(&transaction, &dates),
+            format!(
+                "{} {{{}}} {}",
+                transaction.attributes.created_at.format("%Y-%m-%d"),
+                transaction.attributes.amount.to_string(),
+                transaction.attributes.description
+            )
+        );
+    }
+
+    #[test]
+    fn test_template_errors() {
+        assert!(Template::parse::<Transaction>("{colour}").is_err());
+        assert!(Template::parse::<Transaction>("{description:%Y}").is_err());
+        assert!(Template::parse::<Transaction>("{created_at:%Q}").is_err());
+        assert!(Template::parse::<Transaction>("{description}}").is_err());
+        assert!(Template::parse::<Transaction>("{description").is_err());
+    }
+}
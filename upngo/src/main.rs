@@ -2,18 +2,45 @@ use anyhow::{anyhow, Context, Result};
 use argh::FromArgs;
 use log::*;
 use prettytable::{cell, row, table, Table};
-use upbank::Client;
+use upbank::{Client, ResourceClient};
 use url::Url;
 
+mod config;
+mod dates;
+mod fields;
+mod output;
+mod paging;
+mod resolve;
+
 /// UpBank CLI.
 #[derive(FromArgs)]
 struct Upngo {
-    /// token to authenticate with.
+    /// token to authenticate with. Prefer --token-file or UPBANK_TOKEN, as
+    /// arguments are visible to other processes.
     #[argh(option, short = 't')]
     token: Option<String>,
+    /// file to read the token from, or "-" to read it from stdin.
+    #[argh(option)]
+    token_file: Option<String>,
     /// url to use as base.
-    #[argh(option, default = "default_url()", short = 'u')]
-    url: String,
+    #[argh(option, short = 'u')]
+    url: Option<String>,
+    /// profile from the config file to use.
+    #[argh(option, short = 'p')]
+    profile: Option<String>,
+    /// config file to use instead of ~/.config/upngo/config.toml.
+    #[argh(option)]
+    config: Option<std::path::PathBuf>,
+    /// default output format: table, json, jsonl, csv or yaml.
+    #[argh(option, short = 'o')]
+    output: Option<output::OutputFormat>,
+    /// timezone to show times and interpret dates in, e.g.
+    /// "Australia/Perth". Defaults to Australia/Melbourne.
+    #[argh(option)]
+    tz: Option<chrono_tz::Tz>,
+    /// show dates without times.
+    #[argh(switch)]
+    date_only: bool,
     #[argh(subcommand)]
     subcomand: Subcommand,
 }
@@ -26,14 +53,20 @@ enum Subcommand {
     Register(RegisterCommand),
     Ping(PingCommand),
     Tag(TagCommand),
+    Categorize(CategorizeCommand),
     ListLogs(ListLogCommand),
     Delete(DeleteCommand),
 }
 
-/// Ping UpBank.
+/// Ping UpBank. Exits with 2 if UpBank can't be reached, 3 if the token is
+/// invalid and 4 for any other error from UpBank.
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand, name = "ping")]
 struct PingCommand {
+    /// print the health check as JSON.
+    #[argh(switch)]
+    json: bool,
+
     /// resource to ping.
     #[argh(subcommand)]
     resource: Option<PingResourceCommand>,
@@ -57,6 +90,15 @@ struct TagCommand {
     resource: TagResourceCommand,
 }
 
+/// Categorize a resource.
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "categorize")]
+struct CategorizeCommand {
+    /// resource to categorize.
+    #[argh(subcommand)]
+    resource: CategorizeResourceCommand,
+}
+
 /// List logs.
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand, name = "logs")]
@@ -85,17 +127,40 @@ enum TagResourceCommand {
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand, name = "transaction")]
 struct TagTransaction {
-    /// id of the transaction to tag.
+    /// id of the transaction to tag, or a unique prefix of it from the last
+    /// list of transactions.
     #[argh(positional)]
     transaction_id: String,
     /// tags to add to the transaction.
     #[argh(positional)]
-    tags: Vec<String>,
+    tags: Vec<upbank::tag::TagId>,
     /// delete the specified tags.
     #[argh(switch, short = 'd')]
     delete: bool,
 }
 
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand)]
+enum CategorizeResourceCommand {
+    Transaction(CategorizeTransaction),
+}
+
+/// Set or clear the category of a transaction.
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "transaction")]
+struct CategorizeTransaction {
+    /// id of the transaction to categorize, or a unique prefix of it from
+    /// the last list of transactions.
+    #[argh(positional)]
+    transaction_id: String,
+    /// name or id of the category to set.
+    #[argh(positional)]
+    category: Option<String>,
+    /// remove the transaction's category.
+    #[argh(switch, short = 'c')]
+    clear: bool,
+}
+
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand)]
 enum ListResourceCommand {
@@ -104,6 +169,7 @@ enum ListResourceCommand {
     Categories(ListCategories),
     Tags(ListTags),
     Webhooks(ListWebhooks),
+    Attachments(ListAttachments),
 }
 
 #[derive(FromArgs, PartialEq, Debug)]
@@ -116,60 +182,164 @@ enum ListLogResourceCommand {
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand, name = "transactions")]
 struct ListTransactions {
-    /// max number of transactions to list.
+    /// number of transactions per page.
     #[argh(option, short = 'n')]
     size: Option<u32>,
+    /// list every page rather than just the first.
+    #[argh(switch)]
+    all: bool,
+    /// max number of transactions to list, across pages.
+    #[argh(option)]
+    limit: Option<usize>,
     /// filter transactions by status.
     #[argh(option, short = 's')]
     status: Option<upbank::transaction::Status>,
-    /// filter transactions since the given date.
+    /// filter transactions since the given date, e.g. "yesterday", "7d",
+    /// "last-month", "2024-03", "2024-Q1" or "2024-03-15".
     #[argh(option, short = 'a')]
-    since: Option<chrono::DateTime<chrono::Utc>>,
-    /// filter transactions upto the given date.
+    since: Option<dates::DateSpec>,
+    /// filter transactions upto the end of the given date.
     #[argh(option, short = 'b')]
-    until: Option<chrono::DateTime<chrono::Utc>>,
-    /// filter by category.
+    until: Option<dates::DateSpec>,
+    /// filter transactions within the given period, e.g. "last-month",
+    /// instead of using --since and --until.
+    #[argh(option)]
+    period: Option<dates::DateSpec>,
+    /// filter by category name or ID.
     #[argh(option, short = 'c')]
     category: Option<String>,
-    /// filter by tag.
+    /// filter by tag. Repeat to only list transactions with every tag.
     #[argh(option, short = 't')]
-    tag: Option<String>,
+    tag: Vec<upbank::tag::TagId>,
+    /// only list transactions of the account with this ID, name or alias.
+    #[argh(option)]
+    account: Option<String>,
+    /// output format: table, json, jsonl, csv or yaml.
+    #[argh(option, short = 'o')]
+    output: Option<output::OutputFormat>,
+    /// comma separated fields to show as columns, e.g.
+    /// "description,amount,tags".
+    #[argh(option)]
+    columns: Option<String>,
+    /// print a line per transaction using a template, e.g.
+    /// "{created_at:%Y-%m-%d} {amount} {description}".
+    #[argh(option, long = "format")]
+    template: Option<String>,
+    /// group transactions by the day they were made, with a subtotal for
+    /// each day. Only for table output.
+    #[argh(switch)]
+    by_day: bool,
 }
 
 /// List accounts.
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand, name = "accounts")]
 struct ListAccounts {
-    /// max number of accounts to list.
+    /// number of accounts per page.
     #[argh(option, short = 'n')]
     size: Option<u32>,
+    /// list every page rather than just the first.
+    #[argh(switch)]
+    all: bool,
+    /// max number of accounts to list, across pages.
+    #[argh(option)]
+    limit: Option<usize>,
+    /// filter accounts by type (saver or transactional).
+    #[argh(option, short = 'y')]
+    account_type: Option<upbank::account::AccountType>,
+    /// filter accounts by ownership (individual or joint).
+    #[argh(option)]
+    ownership: Option<upbank::account::OwnershipType>,
+    /// output format: table, json, jsonl, csv or yaml.
+    #[argh(option, short = 'o')]
+    output: Option<output::OutputFormat>,
+    /// comma separated fields to show as columns, e.g.
+    /// "display_name,balance".
+    #[argh(option)]
+    columns: Option<String>,
+    /// print a line per account using a template, e.g.
+    /// "{display_name}: {balance}".
+    #[argh(option, long = "format")]
+    template: Option<String>,
 }
 
 /// List categories.
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand, name = "categories")]
 struct ListCategories {
-    /// filter categories to only those with this parent.
+    /// filter categories to only those with this parent, by name or ID.
     #[argh(option, short = 'p')]
     parent: Option<String>,
+    /// show categories as an indented hierarchy.
+    #[argh(switch)]
+    tree: bool,
+    /// output format: table, json, jsonl, csv or yaml.
+    #[argh(option, short = 'o')]
+    output: Option<output::OutputFormat>,
+    /// comma separated fields to show as columns, e.g.
+    /// "name,parent".
+    #[argh(option)]
+    columns: Option<String>,
+    /// print a line per category using a template, e.g.
+    /// "{name} ({id})".
+    #[argh(option, long = "format")]
+    template: Option<String>,
 }
 
 /// List tags.
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand, name = "tags")]
 struct ListTags {
-    /// max number of tags to show.
+    /// number of tags per page.
     #[argh(option, short = 'n')]
     size: Option<u32>,
+    /// list every page rather than just the first.
+    #[argh(switch)]
+    all: bool,
+    /// max number of tags to list, across pages.
+    #[argh(option)]
+    limit: Option<usize>,
+    /// output format: table, json, jsonl, csv or yaml.
+    #[argh(option, short = 'o')]
+    output: Option<output::OutputFormat>,
 }
 
 /// List webhooks.
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand, name = "webhooks")]
 struct ListWebhooks {
-    /// max number of webhooks to list.
+    /// number of webhooks per page.
+    #[argh(option, short = 'n')]
+    size: Option<u32>,
+    /// list every page rather than just the first.
+    #[argh(switch)]
+    all: bool,
+    /// max number of webhooks to list, across pages.
+    #[argh(option)]
+    limit: Option<usize>,
+    /// output format: table, json, jsonl, csv or yaml.
+    #[argh(option, short = 'o')]
+    output: Option<output::OutputFormat>,
+    /// comma separated fields to show as columns, e.g.
+    /// "url,created_at".
+    #[argh(option)]
+    columns: Option<String>,
+    /// print a line per webhook using a template, e.g.
+    /// "{id} {url}".
+    #[argh(option, long = "format")]
+    template: Option<String>,
+}
+
+/// List attachments.
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "attachments")]
+struct ListAttachments {
+    /// max number of attachments to list.
     #[argh(option, short = 'n')]
     size: Option<u32>,
+    /// output format: table, json, jsonl, csv or yaml.
+    #[argh(option, short = 'o')]
+    output: Option<output::OutputFormat>,
 }
 
 /// List webhook logs.
@@ -178,11 +348,20 @@ struct ListWebhooks {
 struct ListWebhookLogs {
     /// id of the webhook to get the logs for.
     #[argh(positional)]
-    id: String,
+    id: upbank::webhook::WebhookId,
 
-    /// max number of webhooks to list.
+    /// number of logs per page.
     #[argh(option, short = 'n')]
     size: Option<u32>,
+    /// list every page rather than just the first.
+    #[argh(switch)]
+    all: bool,
+    /// max number of logs to list, across pages.
+    #[argh(option)]
+    limit: Option<usize>,
+    /// output format: table, json, jsonl, csv or yaml.
+    #[argh(option, short = 'o')]
+    output: Option<output::OutputFormat>,
 }
 
 /// Get a resource by its ID.
@@ -201,6 +380,8 @@ enum GetResourceCommand {
     Account(GetAccount),
     Category(GetCategory),
     Webhook(GetWebhook),
+    Attachment(GetAttachment),
+    Tag(GetTag),
 }
 
 /// Get a transactions.
@@ -209,16 +390,22 @@ enum GetResourceCommand {
 struct GetTransaction {
     /// id of the transaction to get.
     #[argh(positional)]
-    id: String,
+    id: upbank::transaction::TransactionId,
+    /// output format: table, json, jsonl, csv or yaml.
+    #[argh(option, short = 'o')]
+    output: Option<output::OutputFormat>,
 }
 
 /// Get am account..
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand, name = "account")]
 struct GetAccount {
-    /// id of the account to get.
+    /// id, name or alias of the account to get.
     #[argh(positional)]
     id: String,
+    /// output format: table, json, jsonl, csv or yaml.
+    #[argh(option, short = 'o')]
+    output: Option<output::OutputFormat>,
 }
 
 /// Get a category.
@@ -227,7 +414,10 @@ struct GetAccount {
 struct GetCategory {
     /// id of the category to get.
     #[argh(positional)]
-    id: String,
+    id: upbank::category::CategoryId,
+    /// output format: table, json, jsonl, csv or yaml.
+    #[argh(option, short = 'o')]
+    output: Option<output::OutputFormat>,
 }
 
 /// Get a webhook.
@@ -236,7 +426,40 @@ struct GetCategory {
 struct GetWebhook {
     /// id of the webhook to get.
     #[argh(positional)]
-    id: String,
+    id: upbank::webhook::WebhookId,
+    /// output format: table, json, jsonl, csv or yaml.
+    #[argh(option, short = 'o')]
+    output: Option<output::OutputFormat>,
+}
+
+/// Get a tag and a summary of its transactions.
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "tag")]
+struct GetTag {
+    /// id of the tag to get.
+    #[argh(positional)]
+    id: upbank::tag::TagId,
+    /// number of recent transactions to show.
+    #[argh(option, short = 'n', default = "5")]
+    recent: usize,
+    /// output format: table, json, jsonl, csv or yaml.
+    #[argh(option, short = 'o')]
+    output: Option<output::OutputFormat>,
+}
+
+/// Get an attachment.
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "attachment")]
+struct GetAttachment {
+    /// id of the attachment to get.
+    #[argh(positional)]
+    id: upbank::attachment::AttachmentId,
+    /// download the attachment's file to this path.
+    #[argh(option, short = 'd')]
+    download: Option<std::path::PathBuf>,
+    /// output format: table, json, jsonl, csv or yaml.
+    #[argh(option, short = 'o')]
+    output: Option<output::OutputFormat>,
 }
 
 /// Register a resource.
@@ -264,6 +487,9 @@ struct RegisterWebhook {
     /// description of the webhook.
     #[argh(option, short = 'd')]
     description: Option<String>,
+    /// output format: table, json, jsonl, csv or yaml.
+    #[argh(option, short = 'o')]
+    output: Option<output::OutputFormat>,
 }
 
 #[derive(FromArgs, PartialEq, Debug)]
@@ -278,7 +504,7 @@ enum PingResourceCommand {
 struct PingWebhook {
     /// webhook ID to ping.
     #[argh(positional)]
-    id: String,
+    id: upbank::webhook::WebhookId,
 }
 
 #[derive(FromArgs, PartialEq, Debug)]
@@ -293,7 +519,7 @@ enum DeleteResourceCommand {
 struct DeleteWebhookCommand {
     /// id of the webhook to delete.
     #[argh(positional)]
-    id: String,
+    id: upbank::webhook::WebhookId,
 }
 
 fn default_url() -> String {
@@ -303,51 +529,114 @@ fn default_url() -> String {
 fn main() -> Result<()> {
     pretty_env_logger::init();
 
-    let mut args: Upngo = argh::from_env();
+    let args: Upngo = argh::from_env();
+    let config = match &args.config {
+        Some(path) => config::Config::load(path)?,
+        None => config::Config::load_default()?,
+    };
+    let config_path = args.config.or_else(config::Config::default_path);
+    let profile = config.profile(args.profile.as_deref())?;
+
+    let mut url = args
+        .url
+        .or_else(|| profile.base_url.clone())
+        .unwrap_or_else(default_url);
     // Make sure the URL ends with "/" otherwise URLs won't be built properly.
-    if !args.url.ends_with('/') {
-        args.url = format!("{}/", args.url);
+    if !url.ends_with('/') {
+        url.push('/');
     }
+    let url = Url::parse(&url).with_context(|| format!("Invalid base URL {}", url))?;
 
-    let url = Url::parse(&args.url)?;
-
-    let token = match args.token {
-        Some(token) => token,
-        None => {
+    // A profile picked with --profile takes precedence over UPBANK_TOKEN, but
+    // the default profile doesn't.
+    let env_token = || {
+        std::env::var("UPBANK_TOKEN").ok().map(|token| {
             debug!("Retrieving UpBank token from UPBANK_TOKEN environment variable");
-            std::env::var("UPBANK_TOKEN").expect(
-                "Failed to retrieve UpBank token from flag or UPBANK_TOKEN environment variable",
-            )
-        }
+            upbank::Token::new(token)
+        })
+    };
+    let token = match (args.token, args.token_file) {
+        (Some(token), _) => Some(upbank::Token::new(token)),
+        (None, Some(path)) => Some(config::read_token_file(&path)?),
+        (None, None) if args.profile.is_some() => profile.token()?.or_else(env_token),
+        (None, None) => match env_token() {
+            Some(token) => Some(token),
+            None => profile.token()?,
+        },
     };
+    let token = token.ok_or_else(|| {
+        anyhow!(
+            "No UpBank token given. Use --token-file, set UPBANK_TOKEN or give a profile in {} a token_file or token_command",
+            config_path
+                .as_ref()
+                .map_or_else(|| "the config file".to_string(), |p| p.display().to_string())
+        )
+    })?;
 
     let client = Client::new(url, token);
 
+    let format = args.output.or(profile.output).unwrap_or_default();
+    let dates = dates::DateStyle {
+        tz: args
+            .tz
+            .or(profile.timezone)
+            .unwrap_or(dates::DEFAULT_TIMEZONE),
+        date_only: args.date_only,
+    };
+
     use Subcommand::*;
     match args.subcomand {
-        Get(get) => run_get(client, get),
-        List(list) => run_list(client, list),
-        Register(register) => run_register(client, register),
-        Ping(ping) => run_ping(client, ping),
+        Get(get) => run_get(client, get, &profile, format, dates),
+        List(list) => run_list(client, list, &profile, format, dates),
+        Register(register) => run_register(client, register, format),
+        Ping(ping) => run_ping(client, ping, format),
         Tag(tag) => run_tag(client, tag),
-        ListLogs(logs) => run_list_logs(client, logs),
+        Categorize(categorize) => run_categorize(client, categorize),
+        ListLogs(logs) => run_list_logs(client, logs, format, dates),
         Delete(delete) => run_delete(client, delete),
     }
 }
 
-fn run_ping(client: Client, ping: PingCommand) -> Result<()> {
+fn run_ping(client: Client, ping: PingCommand, format: output::OutputFormat) -> Result<()> {
     if let Some(res) = ping.resource {
         match res {
             PingResourceCommand::Webhook(w) => run_ping_webhook(client, w),
         }
     } else {
-        let resp = client.util.ping()?;
-        match resp {
-            upbank::util::PingResponse::Ok(ping) => {
-                println!("UpBank is up {}", ping.meta.status_emoji);
-                Ok(())
-            }
-            upbank::util::PingResponse::Err(e) => Err(anyhow!("Failed to ping UpBank: {}", e)),
+        let health = client.health();
+        let format = if ping.json {
+            output::OutputFormat::Json
+        } else {
+            format
+        };
+        output::print(format, &health, |health| {
+            let na = || "N/A".to_string();
+            table!(
+                ["Base URL", health.base_url],
+                ["Status", format!("{:?}", health.status)],
+                [
+                    "Latency",
+                    health.latency_ms.map_or_else(na, |l| format!("{}ms", l))
+                ],
+                [
+                    "Clock Skew",
+                    health
+                        .clock_skew_secs
+                        .map_or_else(na, |s| format!("{}s", s))
+                ],
+                [
+                    "Status Emoji",
+                    health.status_emoji.clone().unwrap_or_else(na)
+                ],
+                ["Error", health.error.clone().unwrap_or_else(na)]
+            )
+        })?;
+        use upbank::util::HealthStatus;
+        match health.status {
+            HealthStatus::Healthy => Ok(()),
+            HealthStatus::Unreachable => std::process::exit(2),
+            HealthStatus::InvalidToken => std::process::exit(3),
+            HealthStatus::ApiError => std::process::exit(4),
         }
     }
 }
@@ -367,66 +656,81 @@ fn run_ping_webhook(client: Client, webhook: PingWebhook) -> Result<()> {
     }
 }
 
-fn run_get(client: Client, get: GetCommand) -> Result<()> {
+fn run_get(
+    client: Client,
+    get: GetCommand,
+    profile: &config::Profile,
+    format: output::OutputFormat,
+    dates: dates::DateStyle,
+) -> Result<()> {
     use GetResourceCommand::*;
     match get.resource {
-        Account(account) => run_get_account(client, account),
-        Transaction(transaction) => run_get_transaction(client, transaction),
-        Webhook(webhook) => run_get_webhook(client, webhook),
-        Category(cat) => run_get_category(client, cat),
+        Account(account) => run_get_account(client, account, profile, format, dates),
+        Transaction(transaction) => run_get_transaction(client, transaction, format, dates),
+        Webhook(webhook) => run_get_webhook(client, webhook, format, dates),
+        Category(cat) => run_get_category(client, cat, format),
+        Attachment(attachment) => run_get_attachment(client, attachment, format, dates),
+        Tag(tag) => run_get_tag(client, tag, format, dates),
     }
 }
 
-fn run_get_account(client: Client, account: GetAccount) -> Result<()> {
+fn run_get_account(
+    client: Client,
+    account: GetAccount,
+    profile: &config::Profile,
+    format: output::OutputFormat,
+    dates: dates::DateStyle,
+) -> Result<()> {
+    let id = resolve::account(&client, profile, &account.id)?;
     let resp = client
         .account
-        .get(account.id.clone())
-        .with_context(|| format!("Failed to get account with ID {}", account.id))?;
+        .get(&id)
+        .with_context(|| format!("Failed to get account with ID {}", id))?;
     match resp {
         upbank::response::Response::Ok(acc) => {
-            let attrs: upbank::account::Attributes = acc.data.attributes;
-            let table = table!(
-                ["Name", "Type", "Balance", "Created At"],
-                [
-                    attrs.display_name,
-                    attrs.account_type,
-                    attrs.balance,
-                    attrs.created_at
-                ]
-            );
-            table.printstd();
-            Ok(())
+            output::print(account.output.unwrap_or(format), &acc.data, |acc| {
+                let attrs = &acc.attributes;
+                table!(
+                    ["Name", "Type", "Ownership", "Balance", "Created At"],
+                    [
+                        attrs.display_name,
+                        attrs.account_type,
+                        attrs.ownership_type,
+                        attrs.balance,
+                        dates.format(attrs.created_at)
+                    ]
+                )
+            })
+        }
+        upbank::response::Response::Err(e) => {
+            Err(anyhow!("Failed to get account with ID {}:\n{}", &id, e))
         }
-        upbank::response::Response::Err(e) => Err(anyhow!(
-            "Failed to get account with ID {}:\n{}",
-            &account.id,
-            e
-        )),
     }
 }
 
-fn run_get_transaction(client: Client, transaction: GetTransaction) -> Result<()> {
+fn run_get_transaction(
+    client: Client,
+    transaction: GetTransaction,
+    format: output::OutputFormat,
+    dates: dates::DateStyle,
+) -> Result<()> {
     let resp = client
         .transaction
-        .get(transaction.id.clone())
+        .get(&transaction.id)
         .with_context(|| format!("Failed to get transaction with ID {}", transaction.id))?;
     match resp {
         upbank::response::Response::Ok(transac) => {
-            let attrs = transac.data.attributes;
-            let table = table!(
-                ["Description", "Amount", "Status", "Created", "Settled"],
-                [
-                    attrs.description,
-                    attrs.amount,
-                    attrs.status,
-                    attrs.created_at,
-                    attrs
-                        .settled_at
-                        .map_or_else(|| "N/A".to_string(), |d| d.to_string(),),
-                ]
-            );
-            table.printstd();
-            Ok(())
+            let format = transaction.output.unwrap_or(format);
+            if format != output::OutputFormat::Table {
+                return output::print(format, &transac.data, |_| Table::new());
+            }
+            let expanded = client.expand_transaction(transac.data).with_context(|| {
+                format!(
+                    "Failed to look up relationships of transaction {}",
+                    transaction.id
+                )
+            })?;
+            output::print_table(&transaction_detail(&expanded, &dates))
         }
         upbank::response::Response::Err(e) => Err(anyhow!(
             "Failed to get transaction with ID {}:\n{}",
@@ -436,29 +740,30 @@ fn run_get_transaction(client: Client, transaction: GetTransaction) -> Result<()
     }
 }
 
-fn run_get_webhook(client: Client, webhook: GetWebhook) -> Result<()> {
+fn run_get_webhook(
+    client: Client,
+    webhook: GetWebhook,
+    format: output::OutputFormat,
+    dates: dates::DateStyle,
+) -> Result<()> {
     let resp = client
         .webhook
         .get(&webhook.id)
         .with_context(|| format!("Failed to get webhook with ID {}", webhook.id))?;
     use upbank::response::Response;
     match resp {
-        Response::Ok(w) => {
-            let attrs = w.data.attributes;
-            let table = table!(
+        Response::Ok(w) => output::print(webhook.output.unwrap_or(format), &w.data, |w| {
+            let attrs = &w.attributes;
+            table!(
                 ["Description", "URL", "Created", "ID"],
                 [
-                    attrs
-                        .description
-                        .map_or("None".to_string(), std::convert::identity),
+                    attrs.description.as_deref().unwrap_or("None"),
                     attrs.url,
-                    attrs.created_at,
-                    w.data.id
+                    dates.format(attrs.created_at),
+                    w.id
                 ]
-            );
-            table.printstd();
-            Ok(())
-        }
+            )
+        }),
         Response::Err(e) => Err(anyhow!(
             "Failed to get webhook with ID {}:\n{}",
             &webhook.id,
@@ -467,17 +772,20 @@ fn run_get_webhook(client: Client, webhook: GetWebhook) -> Result<()> {
     }
 }
 
-fn run_get_category(client: Client, category: GetCategory) -> Result<()> {
+fn run_get_category(
+    client: Client,
+    category: GetCategory,
+    format: output::OutputFormat,
+) -> Result<()> {
     let resp = client
         .category
         .get(&category.id)
         .with_context(|| format!("Failed to get category with ID {}", category.id))?;
     match resp {
-        upbank::response::Response::Ok(category) => {
-            let attrs: upbank::category::Attributes = category.data.attributes;
-            let table = table!(["Name"], [attrs.name]);
-            table.printstd();
-            Ok(())
+        upbank::response::Response::Ok(found) => {
+            output::print(category.output.unwrap_or(format), &found.data, |found| {
+                table!(["Name"], [found.attributes.name])
+            })
         }
         upbank::response::Response::Err(e) => Err(anyhow!(
             "Failed to get transaction with ID {}:\n{}",
@@ -487,178 +795,680 @@ fn run_get_category(client: Client, category: GetCategory) -> Result<()> {
     }
 }
 
-fn run_list(client: Client, list: ListCommand) -> Result<()> {
+/// Download `attachment` to `path` through a temporary file next to it, so a
+/// failed download doesn't leave an empty or partial file behind.
+fn download_attachment(
+    client: &Client,
+    attachment: &upbank::attachment::Attachment,
+    path: &std::path::Path,
+) -> Result<u64> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("{} isn't a file path", path.display()))?;
+    let mut part_name = std::ffi::OsString::from(".");
+    part_name.push(file_name);
+    part_name.push(".part");
+    let part = path.with_file_name(part_name);
+
+    let downloaded = std::fs::File::create(&part)
+        .with_context(|| format!("Failed to create file {}", part.display()))
+        .and_then(|mut file| {
+            client
+                .attachment
+                .download(attachment, &mut file)
+                .with_context(|| format!("Failed to download attachment {}", attachment.id))
+        })
+        .and_then(|written| {
+            std::fs::rename(&part, path).with_context(|| {
+                format!("Failed to move {} to {}", part.display(), path.display())
+            })?;
+            Ok(written)
+        });
+    if downloaded.is_err() {
+        let _ = std::fs::remove_file(&part);
+    }
+    downloaded
+}
+
+fn run_get_attachment(
+    client: Client,
+    attachment: GetAttachment,
+    format: output::OutputFormat,
+    dates: dates::DateStyle,
+) -> Result<()> {
+    let resp = client
+        .attachment
+        .get(&attachment.id)
+        .with_context(|| format!("Failed to get attachment with ID {}", attachment.id))?;
+    use upbank::response::Response;
+    match resp {
+        Response::Ok(a) => {
+            if let Some(path) = &attachment.download {
+                let written = download_attachment(&client, &a.data, path)?;
+                println!("Downloaded {} bytes to {}", written, path.display());
+                return Ok(());
+            }
+            output::print(attachment.output.unwrap_or(format), &a.data, |a| {
+                let attrs = &a.attributes;
+                table!(
+                    [
+                        "Created",
+                        "Content Type",
+                        "Transaction",
+                        "URL Expires",
+                        "ID"
+                    ],
+                    [
+                        attrs
+                            .created_at
+                            .map_or_else(|| "N/A".to_string(), |d| dates.format(d)),
+                        attrs.file_content_type.as_deref().unwrap_or("N/A"),
+                        a.relationships
+                            .transaction
+                            .data
+                            .as_ref()
+                            .map_or_else(|| "N/A".to_string(), |t| t.id.to_string()),
+                        dates.format(attrs.file_url_expires_at),
+                        a.id
+                    ]
+                )
+            })
+        }
+        Response::Err(e) => Err(anyhow!(
+            "Failed to get attachment with ID {}:\n{}",
+            &attachment.id,
+            e
+        )),
+    }
+}
+
+/// What `get tag` prints in the machine readable formats.
+#[derive(serde::Serialize)]
+struct TagSummary<'a> {
+    tag: &'a upbank::tag::TagId,
+    transaction_count: usize,
+    total_spend: String,
+    recent_transactions: &'a [upbank::transaction::Transaction],
+}
+
+fn run_get_tag(
+    client: Client,
+    tag: GetTag,
+    format: output::OutputFormat,
+    dates: dates::DateStyle,
+) -> Result<()> {
+    // UpBank can't retrieve a single tag, and there's nothing to a tag but
+    // its ID, so the summary is built from its transactions alone.
+    let resp = client
+        .tag
+        .transactions(&tag.id)
+        .size(100)
+        .exec_all()
+        .with_context(|| format!("Failed to list transactions for tag {}", tag.id))?;
+    let transactions = match resp {
+        upbank::response::Response::Ok(transactions) => transactions.data,
+        upbank::response::Response::Err(e) => {
+            return Err(anyhow!(
+                "Failed to list transactions for tag {}:\n{}",
+                &tag.id,
+                e
+            ))
+        }
+    };
+
+    // Only money going out counts as spending.
+    let spend: i64 = transactions
+        .iter()
+        .filter(|t| t.is_debit())
+        .map(|t| t.amount().value_in_base_units)
+        .sum();
+    // UpBank returns the most recent transactions first.
+    let recent = &transactions[..tag.recent.min(transactions.len())];
+
+    let format = tag.output.unwrap_or(format);
+    if format != output::OutputFormat::Table {
+        let summary = TagSummary {
+            tag: &tag.id,
+            transaction_count: transactions.len(),
+            total_spend: format_base_units(-spend),
+            recent_transactions: recent,
+        };
+        return output::print(format, &summary, |_| Table::new());
+    }
+
+    output::print_table(&table!(
+        ["Tag", "Transactions", "Total Spend"],
+        [tag.id, transactions.len(), format_base_units(-spend)]
+    ))?;
+    let mut table = table!(["Description", "Amount", "Created", "ID"]);
+    for transaction in recent {
+        table.add_row(row![
+            transaction.attributes.description,
+            transaction.attributes.amount,
+            dates.format(transaction.attributes.created_at),
+            transaction.id,
+        ]);
+    }
+    output::print_table(&table)
+}
+
+fn run_list(
+    client: Client,
+    list: ListCommand,
+    profile: &config::Profile,
+    format: output::OutputFormat,
+    dates: dates::DateStyle,
+) -> Result<()> {
     use ListResourceCommand::*;
     match list.resource {
-        Accounts(accounts) => run_list_accounts(client, accounts),
-        Transactions(transactions) => run_list_transactions(client, transactions),
-        Categories(categories) => run_list_categories(client, categories),
-        Tags(tags) => run_list_tags(client, tags),
-        Webhooks(webhooks) => run_list_webhooks(client, webhooks),
+        Accounts(accounts) => run_list_accounts(client, accounts, format, dates),
+        Transactions(transactions) => {
+            run_list_transactions(client, transactions, profile, format, dates)
+        }
+        Categories(categories) => run_list_categories(client, categories, format, dates),
+        Tags(tags) => run_list_tags(client, tags, format),
+        Webhooks(webhooks) => run_list_webhooks(client, webhooks, format, dates),
+        Attachments(attachments) => run_list_attachments(client, attachments, format, dates),
     }
 }
 
-fn run_list_accounts(client: Client, accounts: ListAccounts) -> Result<()> {
+fn run_list_accounts(
+    client: Client,
+    accounts: ListAccounts,
+    format: output::OutputFormat,
+    dates: dates::DateStyle,
+) -> Result<()> {
+    let selection = fields::Selection::parse::<upbank::account::Account>(
+        accounts.columns.as_deref(),
+        accounts.template.as_deref(),
+        dates,
+    )?;
+    let paging = paging::Paging {
+        all: accounts.all,
+        limit: accounts.limit,
+    };
     let mut req = client.account.list();
-    if let Some(size) = accounts.size {
+    if let Some(size) = accounts.size.or_else(|| paging.page_size()) {
         req.size(size);
     }
-    let resp = req.exec().context("Failed to list accounts")?;
-    match resp {
-        upbank::response::Response::Ok(accs) => {
-            let mut table = Table::new();
-            table.add_row(row!["Name", "Balance", "Type", "Created", "ID"]);
-            for acc in accs.data {
-                table.add_row(row![
-                    acc.attributes.display_name,
-                    acc.attributes.balance,
-                    acc.attributes.account_type,
-                    acc.attributes.created_at,
-                    acc.id,
-                ]);
-            }
-            table.printstd();
-            Ok(())
-        }
-        upbank::response::Response::Err(e) => Err(anyhow!("Failed to list accounts:\n{}", e)),
+    if let Some(account_type) = accounts.account_type {
+        req.account_type(account_type);
     }
+    if let Some(ownership) = accounts.ownership {
+        req.ownership_type(ownership);
+    }
+    let format = accounts.output.unwrap_or(format);
+    paging.print(&req, "accounts", selection.streams(format), |accs| {
+        selection.print_list(format, accs)
+    })
 }
 
-fn run_list_transactions(client: Client, transactions: ListTransactions) -> Result<()> {
-    let mut req = client.transaction.list();
+fn run_list_transactions(
+    client: Client,
+    transactions: ListTransactions,
+    profile: &config::Profile,
+    format: output::OutputFormat,
+    dates: dates::DateStyle,
+) -> Result<()> {
+    let selection = fields::Selection::parse::<upbank::transaction::Transaction>(
+        transactions.columns.as_deref(),
+        transactions.template.as_deref(),
+        dates,
+    )?;
+    let paging = paging::Paging {
+        all: transactions.all,
+        limit: transactions.limit,
+    };
+    let mut req = match &transactions.account {
+        Some(account) => {
+            let id = resolve::account(&client, profile, account)?;
+            client.account.transactions(&id)
+        }
+        None => client.transaction.list(),
+    };
 
-    if let Some(size) = transactions.size {
+    if let Some(size) = transactions.size.or_else(|| paging.page_size()) {
         req.size(size);
     }
 
-    if let Some(cat) = transactions.category {
-        req.category(cat);
+    if let Some(cat) = &transactions.category {
+        req.category(resolve::category(&client, cat)?);
     }
 
-    if let Some(since) = transactions.since {
+    let tz = dates.tz;
+    let now = chrono::Utc::now();
+    let (since, until) = match transactions.period {
+        Some(_) if transactions.since.is_some() || transactions.until.is_some() => {
+            return Err(anyhow!("--period can't be used with --since or --until"))
+        }
+        Some(period) if period.is_instant() => {
+            return Err(anyhow!(
+                "--period needs a period like \"last-month\", not a point in time"
+            ))
+        }
+        Some(period) => {
+            let (since, until) = period.range(tz, now).map_err(|e| anyhow!(e))?;
+            (Some(since), Some(until))
+        }
+        None => (
+            transactions
+                .since
+                .map(|since| since.since(tz, now))
+                .transpose()
+                .map_err(|e| anyhow!(e))?,
+            transactions
+                .until
+                .map(|until| until.until(tz, now))
+                .transpose()
+                .map_err(|e| anyhow!(e))?,
+        ),
+    };
+
+    if let Some(since) = since {
         req.since(since);
     }
 
+    if let Some(until) = until {
+        req.until(until);
+    }
+
     if let Some(status) = transactions.status {
         req.status(status);
     }
 
-    let resp = req.exec().context("Failed to list transactions")?;
-    match resp {
-        upbank::response::Response::Ok(transacts) => {
-            let mut table = Table::new();
-            table.add_row(row![
-                "Description",
-                "Amount",
-                "Status",
-                "Created",
-                "Settled",
-                "ID",
-            ]);
-            for transaction in transacts.data {
-                table.add_row(row![
-                    transaction.attributes.description,
-                    transaction.attributes.amount,
-                    transaction.attributes.status,
-                    transaction.attributes.created_at,
-                    transaction
-                        .attributes
-                        .settled_at
-                        .map_or_else(|| "N/A".to_string(), |d| d.to_string(),),
-                    transaction.id,
-                ]);
+    // UpBank only filters by one tag, so the rest are checked here.
+    let mut tags = transactions.tag.into_iter();
+    if let Some(tag) = tags.next() {
+        req.tag(tag);
+    }
+    let other_tags: Vec<_> = tags.collect();
+
+    let keep = |transac: &upbank::transaction::Transaction| {
+        let tag_ids = transac.tag_ids();
+        other_tags.iter().all(|tag| tag_ids.contains(&tag))
+    };
+
+    let format = transactions.output.unwrap_or(format);
+    if transactions.by_day {
+        if format != output::OutputFormat::Table || selection.has_template() {
+            return Err(anyhow!("--by-day only works with table output"));
+        }
+        let found = paging.fetch_all(&req, "transactions", true, keep)?;
+        output::print_table(&day_table(&selection, &found))?;
+        let listed: Vec<_> = found.into_iter().map(|t| t.id).collect();
+        resolve::remember_transactions(&listed);
+        return Ok(());
+    }
+    // So `tag transaction` and friends can take a prefix of these IDs.
+    let listed = std::cell::RefCell::new(vec![]);
+    paging.print_filtered(
+        &req,
+        "transactions",
+        selection.streams(format),
+        keep,
+        |transacts| {
+            listed
+                .borrow_mut()
+                .extend(transacts.iter().map(|t| t.id.clone()));
+            selection.print_list(format, transacts)
+        },
+    )?;
+    resolve::remember_transactions(&listed.into_inner());
+    Ok(())
+}
+
+/// Every attribute of a transaction and the resources it's related to, in
+/// labelled sections.
+fn transaction_detail(
+    expanded: &upbank::expand::ExpandedTransaction,
+    dates: &dates::DateStyle,
+) -> Table {
+    use prettytable::{Cell, Row};
+    let transac = &expanded.transaction;
+    let attrs = &transac.attributes;
+    let na = || "N/A".to_string();
+    let or_na = |value: Option<String>| value.unwrap_or_else(na);
+    let name_and_id = |name: &str, id: &dyn std::fmt::Display| format!("{} ({})", name, id);
+
+    let mut amounts = vec![
+        ("Amount", attrs.amount.to_string()),
+        (
+            "Held Amount",
+            or_na(transac.held_amount().map(|m| m.to_string())),
+        ),
+    ];
+    if let Some(diff) = transac.settled_difference() {
+        amounts.push((
+            "Held Difference",
+            format!("{} {}", format_base_units(diff), attrs.amount.currency_code),
+        ));
+    }
+    amounts.extend(vec![
+        (
+            "Held Foreign Amount",
+            or_na(
+                attrs
+                    .hold_info
+                    .as_ref()
+                    .and_then(|h| h.foreign_amount.as_ref())
+                    .map(|m| m.to_string()),
+            ),
+        ),
+        (
+            "Foreign Amount",
+            or_na(transac.foreign_amount().map(|m| m.to_string())),
+        ),
+        ("FX Rate", or_na(transac.fx_rate().map(|r| r.to_string()))),
+        ("Round Up", or_na(transac.round_up().map(|m| m.to_string()))),
+        (
+            "Boost Portion",
+            or_na(
+                attrs
+                    .round_up
+                    .as_ref()
+                    .and_then(|r| r.boost_portion.as_ref())
+                    .map(|m| m.to_string()),
+            ),
+        ),
+        (
+            "Cashback",
+            or_na(
+                attrs
+                    .cashback
+                    .as_ref()
+                    .map(|c| format!("{} ({})", c.amount.to_string(), c.description)),
+            ),
+        ),
+    ]);
+
+    let tags: Vec<&str> = expanded.tags.iter().map(|t| t.id.as_str()).collect();
+    let sections = vec![
+        (
+            "Transaction",
+            vec![
+                ("ID", transac.id.to_string()),
+                ("Status", attrs.status.to_string()),
+                ("Description", attrs.description.clone()),
+                ("Raw Text", or_na(attrs.raw_text.clone())),
+                ("Message", or_na(attrs.message.clone())),
+                ("Created", dates.format(attrs.created_at)),
+                ("Settled", or_na(attrs.settled_at.map(|d| dates.format(d)))),
+            ],
+        ),
+        ("Amounts", amounts),
+        (
+            "Relationships",
+            vec![
+                (
+                    "Account",
+                    or_na(
+                        expanded
+                            .account
+                            .as_ref()
+                            .map(|a| name_and_id(&a.attributes.display_name, &a.id)),
+                    ),
+                ),
+                (
+                    "Category",
+                    or_na(
+                        expanded
+                            .category
+                            .as_ref()
+                            .map(|c| name_and_id(&c.attributes.name, &c.id)),
+                    ),
+                ),
+                (
+                    "Parent Category",
+                    or_na(
+                        expanded
+                            .parent_category
+                            .as_ref()
+                            .map(|c| name_and_id(&c.attributes.name, &c.id)),
+                    ),
+                ),
+                (
+                    "Tags",
+                    if tags.is_empty() {
+                        na()
+                    } else {
+                        tags.join(", ")
+                    },
+                ),
+            ],
+        ),
+    ];
+
+    let mut table = Table::new();
+    for (title, rows) in sections {
+        table.add_row(Row::new(vec![Cell::new(title).style_spec("bH2")]));
+        for (label, value) in rows {
+            table.add_row(Row::new(vec![Cell::new(label), Cell::new(&value)]));
+        }
+    }
+    table
+}
+
+/// A table of transactions with a subtotal row after each day's
+/// transactions. Days are in the selection's timezone.
+fn day_table(
+    selection: &fields::Selection,
+    transactions: &[upbank::transaction::Transaction],
+) -> Table {
+    use prettytable::{Cell, Row};
+    let columns = selection.columns();
+    let names = columns.names();
+    let amount = names.iter().position(|name| *name == "amount");
+    // Each day's subtotal is labelled in the first column that isn't the
+    // amount, or in a column of its own if there isn't one.
+    let (label, day_column) = match names.iter().position(|name| *name != "amount") {
+        Some(label) => (label, false),
+        None => (0, true),
+    };
+    let offset = usize::from(day_column);
+
+    let mut table = Table::new();
+    let mut header = columns.header();
+    if day_column {
+        header.insert_cell(0, Cell::new("Day"));
+    }
+    table.add_row(header);
+    let days = selection
+        .dates()
+        .group_by_day(transactions, |t| t.attributes.created_at);
+    for (day, transacts) in days {
+        for transac in transacts {
+            let mut values = columns.values(transac, "N/A", selection.dates());
+            if day_column {
+                values.insert(0, String::new());
             }
-            table.printstd();
-            Ok(())
+            table.add_row(Row::new(values.iter().map(|v| Cell::new(v)).collect()));
+        }
+        let mut subtotal = vec![String::new(); names.len() + offset];
+        subtotal[label] = day.format("%a %d %b %Y").to_string();
+        if let Some(amount) = amount {
+            let total: i64 = transacts
+                .iter()
+                .map(|t| t.amount().value_in_base_units)
+                .sum();
+            subtotal[amount + offset] = format!(
+                "{} {}",
+                format_base_units(total),
+                transacts[0].amount().currency_code
+            );
         }
-        upbank::response::Response::Err(e) => Err(anyhow!("Failed to list transactions:\n{}", e)),
+        table.add_row(Row::new(
+            subtotal
+                .iter()
+                .map(|v| Cell::new(v).style_spec("b"))
+                .collect(),
+        ));
     }
+    table
 }
 
-fn run_list_categories(client: Client, categories: ListCategories) -> Result<()> {
+fn run_list_categories(
+    client: Client,
+    categories: ListCategories,
+    format: output::OutputFormat,
+    dates: dates::DateStyle,
+) -> Result<()> {
+    let format = categories.output.unwrap_or(format);
+    let selection = fields::Selection::parse::<upbank::category::Category>(
+        categories.columns.as_deref(),
+        categories.template.as_deref(),
+        dates,
+    )?;
     let mut req = client.category.list();
-    if let Some(parent) = categories.parent {
-        req.parent(parent);
+    if let Some(parent) = &categories.parent {
+        req.parent(resolve::category(&client, parent)?);
     }
     let resp = req.exec().context("Failed to list categories")?;
-    match resp {
-        upbank::response::Response::Ok(categories) => {
-            let mut table = table!(["Name", "ID"]);
-            for category in categories.data {
-                table.add_row(row![category.attributes.name, category.id]);
-            }
-            table.printstd();
-            Ok(())
+    let found = match resp {
+        upbank::response::Response::Ok(found) => found.data,
+        upbank::response::Response::Err(e) => {
+            return Err(anyhow!("Failed to list categories:\n{}", e))
         }
-        upbank::response::Response::Err(e) => Err(anyhow!("Failed to list categories:\n{}", e)),
+    };
+    if !categories.tree || format != output::OutputFormat::Table || categories.template.is_some() {
+        return selection.print_list(format, &found);
+    }
+
+    // Indent names to show the hierarchy.
+    let columns = selection.columns();
+    let name = columns.names().iter().position(|n| *n == "name");
+    let mut table = Table::new();
+    table.add_row(columns.header());
+    let tree = upbank::category::CategoryTree::new(found);
+    for (depth, category) in tree.walk() {
+        let mut values = columns.values(category, "N/A", selection.dates());
+        if let Some(name) = name {
+            values[name] = format!("{}{}", "  ".repeat(depth), values[name]);
+        }
+        table.add_row(values.iter().map(|v| prettytable::Cell::new(v)).collect());
     }
+    output::print_table(&table)
 }
 
-fn run_list_tags(client: Client, tags: ListTags) -> Result<()> {
+fn run_list_tags(client: Client, tags: ListTags, format: output::OutputFormat) -> Result<()> {
+    let paging = paging::Paging {
+        all: tags.all,
+        limit: tags.limit,
+    };
     let mut req = client.tag.list();
-    if let Some(size) = tags.size {
+    if let Some(size) = tags.size.or_else(|| paging.page_size()) {
         req.size(size);
     }
-    let resp = req.exec().context("Failed to list tags")?;
-    match resp {
-        upbank::response::Response::Ok(tags) => {
+    let format = tags.output.unwrap_or(format);
+    let stream = format == output::OutputFormat::Jsonl;
+    paging.print(&req, "tags", stream, |found| {
+        output::print_list(format, found, |found| {
             let mut table = table!(["ID"]);
-            for tag in tags.data {
+            for tag in found {
                 table.add_row(row![tag.id]);
             }
-            table.printstd();
-            Ok(())
-        }
-        upbank::response::Response::Err(e) => Err(anyhow!("Failed to list tags:\n{}", e)),
-    }
+            table
+        })
+    })
 }
 
-fn run_list_webhooks(client: Client, webhooks: ListWebhooks) -> Result<()> {
+fn run_list_webhooks(
+    client: Client,
+    webhooks: ListWebhooks,
+    format: output::OutputFormat,
+    dates: dates::DateStyle,
+) -> Result<()> {
+    let selection = fields::Selection::parse::<upbank::webhook::WebhookResponse>(
+        webhooks.columns.as_deref(),
+        webhooks.template.as_deref(),
+        dates,
+    )?;
+    let paging = paging::Paging {
+        all: webhooks.all,
+        limit: webhooks.limit,
+    };
     let mut req = client.webhook.list();
-    if let Some(size) = webhooks.size {
+    if let Some(size) = webhooks.size.or_else(|| paging.page_size()) {
+        req.size(size);
+    }
+    let format = webhooks.output.unwrap_or(format);
+    paging.print(&req, "webhooks", selection.streams(format), |found| {
+        selection.print_list(format, found)
+    })
+}
+
+fn run_list_attachments(
+    client: Client,
+    attachments: ListAttachments,
+    format: output::OutputFormat,
+    dates: dates::DateStyle,
+) -> Result<()> {
+    let mut req = client.attachment.list();
+    if let Some(size) = attachments.size {
         req.size(size);
     }
-    let resp = req.exec().context("Failed to list webhooks")?;
+    let resp = req.exec().context("Failed to list attachments")?;
     match resp {
-        upbank::response::Response::Ok(webhooks) => {
-            let mut table = Table::new();
-            table.add_row(row!["Description", "URL", "Created", "ID"]);
-            for webhook in webhooks.data {
-                table.add_row(row![
-                    webhook.attributes.url,
-                    webhook
-                        .attributes
-                        .description
-                        .map_or_else(|| "N/A".to_string(), std::convert::identity),
-                    webhook.attributes.created_at,
-                    webhook.id,
-                ]);
-            }
-            table.printstd();
-            Ok(())
+        upbank::response::Response::Ok(found) => {
+            output::print_list(attachments.output.unwrap_or(format), &found.data, |found| {
+                let mut table = table!(["Created", "Content Type", "Transaction", "ID"]);
+                for attachment in found {
+                    table.add_row(row![
+                        attachment
+                            .attributes
+                            .created_at
+                            .map_or_else(|| "N/A".to_string(), |d| dates.format(d)),
+                        attachment
+                            .attributes
+                            .file_content_type
+                            .as_deref()
+                            .unwrap_or("N/A"),
+                        attachment
+                            .relationships
+                            .transaction
+                            .data
+                            .as_ref()
+                            .map_or_else(|| "N/A".to_string(), |t| t.id.to_string()),
+                        attachment.id,
+                    ]);
+                }
+                table
+            })
         }
-        upbank::response::Response::Err(e) => Err(anyhow!("Failed to list webhooks:\n{}", e)),
+        upbank::response::Response::Err(e) => Err(anyhow!("Failed to list attachments:\n{}", e)),
     }
 }
 
-fn run_list_logs(client: Client, logs: ListLogCommand) -> Result<()> {
+fn run_list_logs(
+    client: Client,
+    logs: ListLogCommand,
+    format: output::OutputFormat,
+    dates: dates::DateStyle,
+) -> Result<()> {
     use ListLogResourceCommand::*;
     match logs.resource {
-        Webhooks(webhooks) => run_list_webhook_logs(client, webhooks),
+        Webhooks(webhooks) => run_list_webhook_logs(client, webhooks, format, dates),
     }
 }
 
-fn run_list_webhook_logs(client: Client, webhooks: ListWebhookLogs) -> Result<()> {
+fn run_list_webhook_logs(
+    client: Client,
+    webhooks: ListWebhookLogs,
+    format: output::OutputFormat,
+    dates: dates::DateStyle,
+) -> Result<()> {
+    let paging = paging::Paging {
+        all: webhooks.all,
+        limit: webhooks.limit,
+    };
     let mut req = client.webhook.logs(&webhooks.id);
-    if let Some(size) = webhooks.size {
+    if let Some(size) = webhooks.size.or_else(|| paging.page_size()) {
         req.size(size);
     }
-    let resp = req
-        .exec()
-        .with_context(|| format!("Failed to get logs for webhook with ID {}", webhooks.id))?;
-    use upbank::response::Response;
-    match resp {
-        Response::Ok(w) => {
+    let format = webhooks.output.unwrap_or(format);
+    let stream = format == output::OutputFormat::Jsonl;
+    let what = format!("logs for webhook {}", webhooks.id);
+    paging.print(&req, &what, stream, |records| {
+        output::print_list(format, records, |records| {
             let mut table = table!([
                 "Time",
                 "Request",
@@ -667,51 +1477,59 @@ fn run_list_webhook_logs(client: Client, webhooks: ListWebhookLogs) -> Result<()
                 "Status",
                 "ID"
             ]);
-            for record in w.data {
+            for record in records {
                 table.add_row(row![
-                    record.attributes.created_at,
-                    truncate(record.attributes.request.body, 10),
+                    dates.format(record.attributes.created_at),
+                    truncate(record.attributes.request.body.clone(), 10),
                     record.attributes.response.status_code,
-                    truncate(record.attributes.response.body, 10),
+                    truncate(record.attributes.response.body.clone(), 10),
                     record.attributes.delivery_status,
                     record.id
                 ]);
             }
-            table.printstd();
-            Ok(())
-        }
-        Response::Err(e) => Err(anyhow!(
-            "Failed to get logs for webhook with ID {}: {}",
-            webhooks.id,
-            e
-        )),
-    }
+            table
+        })
+    })
 }
 
-fn run_register(client: Client, register: RegisterCommand) -> Result<()> {
+fn run_register(
+    client: Client,
+    register: RegisterCommand,
+    format: output::OutputFormat,
+) -> Result<()> {
     use RegisterResourceCommand::*;
     match register.resource {
-        Webhook(webhook) => run_register_webhook(client, webhook),
+        Webhook(webhook) => run_register_webhook(client, webhook, format),
     }
 }
 
-fn run_register_webhook(client: Client, webhook: RegisterWebhook) -> Result<()> {
+fn run_register_webhook(
+    client: Client,
+    webhook: RegisterWebhook,
+    format: output::OutputFormat,
+) -> Result<()> {
     let w = upbank::webhook::Webhook::new(webhook.url, webhook.description);
     let resp = client.webhook.register(&w)?;
     use upbank::response::Response;
     match resp {
-        Response::Ok(new_webhook) => {
-            let table = table!(
-                ["URL", "Description", "Secret Key"],
-                [
-                    new_webhook.data.attributes.url,
-                    new_webhook.data.attributes.description.map_or("None".to_string(), std::convert::identity),
-                    new_webhook.data.attributes.secret_key.expect("Secret key must be provided on registration")
-                ]
-            );
-            table.printstd();
-            Ok(())
-        }
+        Response::Ok(new_webhook) => output::print(
+            webhook.output.unwrap_or(format),
+            &new_webhook.data,
+            |new_webhook| {
+                let attrs = &new_webhook.attributes;
+                table!(
+                    ["URL", "Description", "Secret Key"],
+                    [
+                        attrs.url,
+                        attrs.description.as_deref().unwrap_or("None"),
+                        attrs
+                            .secret_key
+                            .as_deref()
+                            .expect("Secret key must be provided on registration")
+                    ]
+                )
+            },
+        ),
         Response::Err(e) => Err(anyhow!("Failed to create webhook {:?}: {}", w, e)),
     }
 }
@@ -724,7 +1542,7 @@ fn run_tag(client: Client, tag: TagCommand) -> Result<()> {
 }
 
 fn run_tag_transaction(client: Client, tag: TagTransaction) -> Result<()> {
-    let id = tag.transaction_id;
+    let id = resolve::transaction(&tag.transaction_id)?;
     let tags = tag.tags.clone();
 
     if tag.delete {
@@ -740,6 +1558,45 @@ fn run_tag_transaction(client: Client, tag: TagTransaction) -> Result<()> {
     }
 }
 
+fn run_categorize(client: Client, categorize: CategorizeCommand) -> Result<()> {
+    use CategorizeResourceCommand::*;
+    match categorize.resource {
+        Transaction(transaction) => run_categorize_transaction(client, transaction),
+    }
+}
+
+fn run_categorize_transaction(client: Client, categorize: CategorizeTransaction) -> Result<()> {
+    let id = resolve::transaction(&categorize.transaction_id)?;
+    // The categories are listed once, both to resolve the name and to check
+    // it's a category transactions can be given.
+    let (category, categories) = match (categorize.category, categorize.clear) {
+        (Some(category), false) => {
+            let categories = resolve::categories(&client)?;
+            (
+                Some(resolve::category_in(&categories, &category)?),
+                categories,
+            )
+        }
+        (None, true) => (None, vec![]),
+        (Some(_), true) => return Err(anyhow!("Cannot set and clear a category at the same time")),
+        (None, false) => return Err(anyhow!("Either a category or --clear must be given")),
+    };
+    client
+        .transaction
+        .categorize_with(&id, category.clone(), &categories)
+        .with_context(|| {
+            format!(
+                "Failed to set category {:?} on transaction {}",
+                category, id
+            )
+        })?;
+    match category {
+        Some(category) => println!("Categorized transaction {} as {}", id, category),
+        None => println!("Cleared category of transaction {}", id),
+    }
+    Ok(())
+}
+
 fn run_delete(client: Client, delete: DeleteCommand) -> Result<()> {
     use DeleteResourceCommand::*;
     match delete.resource {
@@ -766,3 +1623,10 @@ fn truncate(msg: String, max_length: usize) -> String {
         truncated
     }
 }
+
+/// Format an amount in cents as dollars.
+fn format_base_units(value: i64) -> String {
+    let sign = if value < 0 { "-" } else { "" };
+    let value = value.abs();
+    format!("{}{}.{:02}", sign, value / 100, value % 100)
+}
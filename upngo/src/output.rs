@@ -0,0 +1,208 @@
+//! Printing resources, either as a table for people or in a machine readable
+//! format for scripts. The machine readable formats contain the whole
+//! resource, as returned by UpBank, rather than just the table's columns.
+
+use anyhow::{anyhow, Result};
+use prettytable::Table;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    /// A single JSON document, an array when listing.
+    Json,
+    /// One JSON object per line.
+    Jsonl,
+    /// A row per resource, with a column for each (dotted) field path.
+    Csv,
+    Yaml,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            "jsonl" => Ok(OutputFormat::Jsonl),
+            "csv" => Ok(OutputFormat::Csv),
+            "yaml" => Ok(OutputFormat::Yaml),
+            _ => Err(format!(
+                "Unknown output format '{}', expected table, json, jsonl, csv or yaml",
+                s
+            )),
+        }
+    }
+}
+
+/// Print a single resource. `to_table` is only called for the table format.
+pub fn print<T: Serialize>(
+    format: OutputFormat,
+    resource: &T,
+    to_table: impl FnOnce(&T) -> Table,
+) -> Result<()> {
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    match format {
+        OutputFormat::Table => {
+            print_table(&to_table(resource))?;
+        }
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, resource)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Jsonl => {
+            serde_json::to_writer(&mut out, resource)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => write_csv(&mut out, std::slice::from_ref(resource))?,
+        OutputFormat::Yaml => serde_yaml::to_writer(&mut out, resource)?,
+    }
+    Ok(())
+}
+
+/// Print a table that doesn't have a machine readable equivalent.
+pub fn print_table(table: &Table) -> Result<()> {
+    table.print(&mut std::io::stdout().lock())?;
+    Ok(())
+}
+
+/// Print a list of resources. `to_table` is only called for the table
+/// format.
+pub fn print_list<T: Serialize>(
+    format: OutputFormat,
+    resources: &[T],
+    to_table: impl FnOnce(&[T]) -> Table,
+) -> Result<()> {
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    match format {
+        OutputFormat::Table => {
+            print_table(&to_table(resources))?;
+        }
+        OutputFormat::Jsonl => {
+            for resource in resources {
+                serde_json::to_writer(&mut out, resource)?;
+                writeln!(out)?;
+            }
+        }
+        OutputFormat::Csv => write_csv(&mut out, resources)?,
+        // An array for the formats that are a single document.
+        OutputFormat::Json | OutputFormat::Yaml => print(format, &resources, |_| Table::new())?,
+    }
+    Ok(())
+}
+
+/// Print rows of values as CSV.
+pub fn print_csv(header: &[&str], rows: &[Vec<String>]) -> Result<()> {
+    let mut writer = csv::Writer::from_writer(std::io::stdout());
+    writer.write_record(header)?;
+    for row in rows {
+        writer.write_record(row)?;
+    }
+    writer
+        .flush()
+        .map_err(|e| anyhow!("Failed to write CSV: {}", e))
+}
+
+/// Write a row per resource. The columns are the union of the resources'
+/// field paths, in sorted order so they're stable.
+fn write_csv<W: Write, T: Serialize>(out: W, resources: &[T]) -> Result<()> {
+    let rows = resources
+        .iter()
+        .map(|resource| {
+            let mut row = BTreeMap::new();
+            flatten(String::new(), serde_json::to_value(resource)?, &mut row);
+            Ok(row)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let columns: BTreeSet<&String> = rows.iter().flat_map(|row| row.keys()).collect();
+
+    let mut writer = csv::Writer::from_writer(out);
+    writer.write_record(&columns)?;
+    for row in &rows {
+        writer.write_record(
+            columns
+                .iter()
+                .map(|column| row.get(*column).map_or("", String::as_str)),
+        )?;
+    }
+    writer
+        .flush()
+        .map_err(|e| anyhow!("Failed to write CSV: {}", e))
+}
+
+/// Flatten `value` into `row`, keyed by the dotted path to each scalar, e.g.
+/// "attributes.amount.value". Array elements are keyed by their index.
+fn flatten(path: String, value: serde_json::Value, row: &mut BTreeMap<String, String>) {
+    use serde_json::Value;
+    let join = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+    match value {
+        Value::Object(fields) => {
+            for (key, value) in fields {
+                flatten(join(&key), value, row);
+            }
+        }
+        Value::Array(values) => {
+            for (i, value) in values.into_iter().enumerate() {
+                flatten(join(&i.to_string()), value, row);
+            }
+        }
+        Value::Null => {
+            row.insert(path, String::new());
+        }
+        Value::String(s) => {
+            row.insert(path, s);
+        }
+        value => {
+            row.insert(path, value.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{write_csv, OutputFormat};
+    use serde_json::json;
+
+    #[test]
+    fn test_output_format_from_str() {
+        assert_eq!("JSONL".parse(), Ok(OutputFormat::Jsonl));
+        assert_eq!("yaml".parse(), Ok(OutputFormat::Yaml));
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_csv() {
+        let resources = vec![
+            json!({
+                "id": "1",
+                "attributes": {"amount": {"value": "-4.50"}, "settledAt": null},
+                "tags": ["coffee", "work"]
+            }),
+            json!({
+                "id": "2",
+                "attributes": {"amount": {"value": "10.00"}, "settledAt": "2020-07-01"}
+            }),
+        ];
+        let mut out = vec![];
+        write_csv(&mut out, &resources).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "attributes.amount.value,attributes.settledAt,id,tags.0,tags.1\n\
+             -4.50,,1,coffee,work\n\
+             10.00,2020-07-01,2,,\n"
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! Following `links.next` for the list commands' `--all` and `--limit`.
+
+use anyhow::{anyhow, Context, Result};
+use serde::de::DeserializeOwned;
+use std::fmt::Debug;
+use std::io::{IsTerminal, Write};
+use upbank::endpoint::ListRequestBuilder;
+use upbank::response::Response;
+
+/// Largest page size UpBank accepts.
+const MAX_PAGE_SIZE: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Paging {
+    /// Fetch every page rather than just the first.
+    pub all: bool,
+    /// Stop after this many resources, across however many pages it takes.
+    pub limit: Option<usize>,
+}
+
+impl Paging {
+    /// Page size to use if one wasn't given, so a small limit doesn't fetch
+    /// more than it needs and a large one doesn't take more requests than
+    /// it needs.
+    pub fn page_size(&self) -> Option<u32> {
+        self.limit.map(|limit| limit.clamp(1, MAX_PAGE_SIZE) as u32)
+    }
+
+    /// Fetch the resources listed by `req` that `keep` accepts, passing each
+    /// page to `on_page` as it arrives. Returns how many resources were kept.
+    ///
+    /// Only the first page is fetched unless `all` or `limit` is set. `keep`
+    /// is for filters UpBank can't apply itself, and is applied before
+    /// `limit` so the limit counts matching resources. With `progress`,
+    /// progress is shown on stderr while following pages if it's a terminal.
+    pub fn fetch<R: DeserializeOwned + Debug>(
+        &self,
+        req: &ListRequestBuilder<R>,
+        what: &str,
+        progress: bool,
+        keep: impl Fn(&R) -> bool,
+        mut on_page: impl FnMut(Vec<R>) -> Result<()>,
+    ) -> Result<usize> {
+        let follow = self.all || self.limit.is_some();
+        let show_progress = progress && follow && std::io::stderr().is_terminal();
+        let mut fetched = 0;
+        for (i, page) in req.pages().enumerate() {
+            let mut page = match page.with_context(|| format!("Failed to list {}", what))? {
+                Response::Ok(page) => page.data,
+                Response::Err(e) => return Err(anyhow!("Failed to list {}:\n{}", what, e)),
+            };
+            page.retain(&keep);
+            if let Some(limit) = self.limit {
+                page.truncate(limit - fetched);
+            }
+            fetched += page.len();
+            if show_progress {
+                eprint!("\rFetched {} {} from {} pages", fetched, what, i + 1);
+            }
+            on_page(page)?;
+            if !follow || matches!(self.limit, Some(limit) if fetched >= limit) {
+                break;
+            }
+        }
+        if show_progress {
+            eprintln!();
+            std::io::stderr().flush()?;
+        }
+        Ok(fetched)
+    }
+
+    /// Fetch the resources listed by `req` and print them with `print`. With
+    /// `stream` each page is printed as it arrives, otherwise they're all
+    /// printed together, e.g. so they're in one table. Progress isn't shown
+    /// when streaming to a terminal, as it'd be mixed up with the output.
+    pub fn print<R: DeserializeOwned + Debug>(
+        &self,
+        req: &ListRequestBuilder<R>,
+        what: &str,
+        stream: bool,
+        print: impl Fn(&[R]) -> Result<()>,
+    ) -> Result<()> {
+        self.print_filtered(req, what, stream, |_| true, print)
+    }
+
+    /// Like `print`, but only prints the resources `keep` accepts.
+    pub fn print_filtered<R: DeserializeOwned + Debug>(
+        &self,
+        req: &ListRequestBuilder<R>,
+        what: &str,
+        stream: bool,
+        keep: impl Fn(&R) -> bool,
+        print: impl Fn(&[R]) -> Result<()>,
+    ) -> Result<()> {
+        if stream {
+            let progress = !std::io::stdout().is_terminal();
+            self.fetch(req, what, progress, keep, |page| print(&page))?;
+            Ok(())
+        } else {
+            print(&self.fetch_all(req, what, true, keep)?)
+        }
+    }
+
+    /// Fetch all the resources listed by `req` that `keep` accepts into one
+    /// `Vec`, showing progress like `fetch`.
+    pub fn fetch_all<R: DeserializeOwned + Debug>(
+        &self,
+        req: &ListRequestBuilder<R>,
+        what: &str,
+        progress: bool,
+        keep: impl Fn(&R) -> bool,
+    ) -> Result<Vec<R>> {
+        let mut resources = vec![];
+        self.fetch(req, what, progress, keep, |mut page| {
+            resources.append(&mut page);
+            Ok(())
+        })?;
+        Ok(resources)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Paging;
+
+    #[test]
+    fn test_page_size() {
+        let paging = |limit| Paging { all: false, limit };
+        assert_eq!(paging(None).page_size(), None);
+        assert_eq!(paging(Some(0)).page_size(), Some(1));
+        assert_eq!(paging(Some(30)).page_size(), Some(30));
+        assert_eq!(paging(Some(5000)).page_size(), Some(100));
+    }
+}
@@ -0,0 +1,234 @@
+//! Turning what people type on the command line into IDs, so accounts and
+//! categories can be given by name and transactions by a prefix of their ID.
+//!
+//! Names are matched ignoring case, spaces, punctuation and emoji, so
+//! "spending" matches "💸 Spending". A query that matches more than one
+//! resource is an error rather than a guess.
+
+use crate::{config, paging};
+use anyhow::{anyhow, Context, Result};
+use log::*;
+use std::path::PathBuf;
+use upbank::{
+    account::AccountId,
+    category::{Category, CategoryId},
+    transaction::TransactionId,
+    Client, ResourceClient,
+};
+
+/// Length of a full transaction ID, a UUID.
+const ID_LEN: usize = 36;
+
+/// Most transaction IDs kept from the last `list transactions`.
+const MAX_REMEMBERED: usize = 1000;
+
+/// The ID of the account `query` refers to: an alias from the profile, or an
+/// account's ID, unique ID prefix or name.
+pub fn account(client: &Client, profile: &config::Profile, query: &str) -> Result<AccountId> {
+    if let Some(id) = profile.resolve_alias(query) {
+        return Ok(AccountId::new(id));
+    }
+    let mut req = client.account.list();
+    req.size(100);
+    // Resolving a name is a detail of another command, so it doesn't show
+    // progress.
+    let accounts = paging::Paging {
+        all: true,
+        limit: None,
+    }
+    .fetch_all(&req, "accounts", false, |_| true)?;
+    let candidates: Vec<(&str, &str)> = accounts
+        .iter()
+        .map(|a| (a.id.as_str(), a.attributes.display_name.as_str()))
+        .collect();
+    find("account", query, &candidates).map(AccountId::new)
+}
+
+/// The ID of the category `query` refers to, by ID, unique ID prefix or
+/// name.
+pub fn category(client: &Client, query: &str) -> Result<CategoryId> {
+    category_in(&categories(client)?, query)
+}
+
+/// Every category, to resolve names against with `category_in`.
+pub fn categories(client: &Client) -> Result<Vec<Category>> {
+    let resp = client
+        .category
+        .list()
+        .exec()
+        .context("Failed to list categories")?;
+    match resp {
+        upbank::response::Response::Ok(categories) => Ok(categories.data),
+        upbank::response::Response::Err(e) => Err(anyhow!("Failed to list categories:\n{}", e)),
+    }
+}
+
+/// Like `category`, but resolving against categories that were already
+/// listed.
+pub fn category_in(categories: &[Category], query: &str) -> Result<CategoryId> {
+    let candidates: Vec<(&str, &str)> = categories
+        .iter()
+        .map(|c| (c.id.as_str(), c.attributes.name.as_str()))
+        .collect();
+    find("category", query, &candidates).map(CategoryId::new)
+}
+
+/// The ID of the transaction `query` refers to. A prefix is resolved against
+/// the transactions shown by the last `list transactions`.
+pub fn transaction(query: &str) -> Result<TransactionId> {
+    if query.is_empty() {
+        return Err(anyhow!("No transaction ID given"));
+    }
+    let remembered = read_remembered();
+    let found: Vec<&String> = remembered
+        .iter()
+        .filter(|id| id.starts_with(query))
+        .collect();
+    match found.as_slice() {
+        [id] => Ok(TransactionId::new(id.as_str())),
+        // It may be a full ID that just wasn't listed recently.
+        [] if query.len() == ID_LEN => Ok(TransactionId::new(query)),
+        [] => Err(anyhow!(
+            "No recently listed transaction has an ID starting with '{}'. Give the full ID, or list transactions first to use a prefix",
+            query
+        )),
+        found => Err(anyhow!(
+            "'{}' matches {} recently listed transactions, give more of the ID",
+            query,
+            found.len()
+        )),
+    }
+}
+
+/// Remember the IDs of listed transactions so `transaction` can resolve
+/// prefixes of them. Failing to is only logged, as listing still worked.
+pub fn remember_transactions(ids: &[TransactionId]) {
+    let path = match cache_path() {
+        Some(path) => path,
+        None => return,
+    };
+    let contents: String = ids
+        .iter()
+        .take(MAX_REMEMBERED)
+        .map(|id| format!("{}\n", id))
+        .collect();
+    let written = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&path, contents));
+    if let Err(e) = written {
+        debug!("Failed to write {}: {}", path.display(), e);
+    }
+}
+
+fn read_remembered() -> Vec<String> {
+    cache_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .map(|contents| contents.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// `$XDG_CACHE_HOME/upngo/transactions`, falling back to
+/// `~/.cache/upngo/transactions`.
+fn cache_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| config::home_dir().map(|home| home.join(".cache")))
+        .map(|dir| dir.join("upngo").join("transactions"))
+}
+
+/// Whether a candidate's ID and name match a query.
+type Matcher<'a> = &'a dyn Fn(&str, &str) -> bool;
+
+/// Find the ID of the candidate, an `(id, name)` pair, that `query` refers
+/// to. Tries, in order, an exact ID, an exact name, then IDs starting with
+/// or names containing `query`, then names containing its letters in order.
+/// The first of these that matches anything must match exactly one.
+fn find<'a>(what: &str, query: &str, candidates: &[(&'a str, &'a str)]) -> Result<&'a str> {
+    if query.is_empty() {
+        return Err(anyhow!("No {} given", what));
+    }
+    let query_name = normalize(query);
+    // A query of only emoji or punctuation can only match by ID.
+    let by_name = !query_name.is_empty();
+    let tiers: [Matcher; 4] = [
+        &|id, _| id == query,
+        &|_, name| by_name && normalize(name) == query_name,
+        &|id, name| id.starts_with(query) || (by_name && normalize(name).contains(&query_name)),
+        &|_, name| by_name && is_subsequence(&query_name, &normalize(name)),
+    ];
+    for matches in tiers.iter() {
+        let found: Vec<&(&str, &str)> = candidates
+            .iter()
+            .filter(|(id, name)| matches(id, name))
+            .collect();
+        match found.as_slice() {
+            [] => continue,
+            [(id, _)] => return Ok(id),
+            found => {
+                let found: Vec<String> = found
+                    .iter()
+                    .map(|(id, name)| format!("{} ({})", name, id))
+                    .collect();
+                return Err(anyhow!(
+                    "'{}' matches more than one {}: {}",
+                    query,
+                    what,
+                    found.join(", ")
+                ));
+            }
+        }
+    }
+    Err(anyhow!(
+        "No {} with a name or ID matching '{}'",
+        what,
+        query
+    ))
+}
+
+/// Lowercase with only letters and digits kept.
+fn normalize(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Whether the characters of `needle` appear in `haystack` in order.
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle.chars().all(|c| haystack.any(|h| h == c))
+}
+
+#[cfg(test)]
+mod test {
+    use super::find;
+
+    const ACCOUNTS: &[(&str, &str)] = &[
+        ("2b4b3e4c-0000", "💸 Spending"),
+        ("2b4b9a1f-0000", "Savings"),
+        ("9c1d77e0-0000", "Holiday Savings"),
+    ];
+
+    #[test]
+    fn test_find() {
+        let find = |query| find("account", query, ACCOUNTS).map_err(|e| e.to_string());
+        assert_eq!(find("9c1d77e0-0000"), Ok("9c1d77e0-0000"));
+        assert_eq!(find("spending"), Ok("2b4b3e4c-0000"));
+        // An exact name beats one that contains it.
+        assert_eq!(find("SAVINGS"), Ok("2b4b9a1f-0000"));
+        assert_eq!(find("holiday"), Ok("9c1d77e0-0000"));
+        assert_eq!(find("2b4b9"), Ok("2b4b9a1f-0000"));
+        assert_eq!(find("spnd"), Ok("2b4b3e4c-0000"));
+        assert_eq!(
+            find("2b4b"),
+            Err("'2b4b' matches more than one account: \
+                 💸 Spending (2b4b3e4c-0000), Savings (2b4b9a1f-0000)"
+                .to_string())
+        );
+        assert!(find("sav").is_err());
+        assert!(find("loan").is_err());
+        assert!(find("").is_err());
+    }
+}